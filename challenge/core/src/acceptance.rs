@@ -0,0 +1,148 @@
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// Acceptance selects which candidates replace the current shapes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Acceptance {
+    /// keeps a candidate only when it is closer to the target, as the baseline does
+    #[default]
+    Greedy,
+    /// simulated annealing, which also keeps worse candidates now and then
+    /// to escape local optima
+    Annealing(Annealing),
+}
+
+impl Acceptance {
+    /// accept tells whether a candidate at the given distance replaces the
+    /// current shapes, at the given distance, during the given epoch
+    pub fn accept<R: RngCore + ?Sized>(&self, rng: &mut R, current: f64, candidate: f64, epoch: u64) -> bool {
+        if candidate < current {
+            return true;
+        }
+        match self {
+            Acceptance::Greedy => false,
+            Acceptance::Annealing(annealing) => {
+                // Metropolis criterion, a candidate worse by delta is kept with probability exp(-delta / T)
+                let probability = (-(candidate - current) / annealing.temperature(epoch)).exp();
+                rng.gen::<f64>() < probability
+            }
+        }
+    }
+
+    /// temperature returns the temperature of simulated annealing during the epoch, 0 when greedy
+    pub fn temperature(&self, epoch: u64) -> f64 {
+        match self {
+            Acceptance::Greedy => 0.0,
+            Acceptance::Annealing(annealing) => annealing.temperature(epoch),
+        }
+    }
+}
+
+/// Annealing holds the temperature schedule of simulated annealing, the
+/// temperature being in the unit of the distance to the target
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Annealing {
+    /// how the temperature goes from start to end
+    pub schedule: Schedule,
+    /// temperature of the first epoch
+    pub start: f64,
+    /// temperature reached after the given number of epochs
+    pub end: f64,
+    /// number of epochs the temperature takes to cool down from start to end
+    pub epochs: u64,
+}
+
+/// Schedule is the way the temperature of simulated annealing decreases
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    /// decreases by the same amount each epoch, then stays at the end temperature
+    Linear,
+    /// decreases by the same factor each epoch, then stays at the end temperature
+    Exponential,
+    /// decreases exponentially, then goes back to the start temperature and
+    /// cools down again, so the search is shaken up every cycle
+    Reheating,
+}
+
+impl Annealing {
+    /// temperature returns the temperature during the epoch
+    pub fn temperature(&self, epoch: u64) -> f64 {
+        let epochs = self.epochs.max(1);
+        let progress = match self.schedule {
+            Schedule::Linear | Schedule::Exponential => epoch.min(epochs) as f64 / epochs as f64,
+            Schedule::Reheating => (epoch % epochs) as f64 / epochs as f64,
+        };
+        match self.schedule {
+            Schedule::Linear => self.start + (self.end - self.start) * progress,
+            Schedule::Exponential | Schedule::Reheating => self.start * (self.end / self.start).powf(progress),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn annealing(schedule: Schedule) -> Annealing {
+        Annealing { schedule, start: 8.0, end: 0.5, epochs: 100 }
+    }
+
+    // assert_close checks that 2 temperatures are equal up to rounding
+    fn assert_close(found: f64, expected: f64) {
+        assert!((found - expected).abs() < 1e-9, "{found} is not {expected}");
+    }
+
+    #[test]
+    fn linear_schedule_cools_by_steps_then_stays() {
+        let annealing = annealing(Schedule::Linear);
+        assert_close(annealing.temperature(0), 8.0);
+        assert_close(annealing.temperature(50), 4.25);
+        assert_close(annealing.temperature(100), 0.5);
+        assert_close(annealing.temperature(1000), 0.5);
+    }
+
+    #[test]
+    fn exponential_schedule_cools_by_factors_then_stays() {
+        let annealing = annealing(Schedule::Exponential);
+        assert_close(annealing.temperature(0), 8.0);
+        // half the epochs cool down by the square root of the whole factor
+        assert_close(annealing.temperature(50), 2.0);
+        assert_close(annealing.temperature(100), 0.5);
+        assert_close(annealing.temperature(1000), 0.5);
+    }
+
+    #[test]
+    fn reheating_schedule_starts_over_every_cycle() {
+        let annealing = annealing(Schedule::Reheating);
+        assert_close(annealing.temperature(50), 2.0);
+        assert_close(annealing.temperature(99), annealing.temperature(199));
+        assert_close(annealing.temperature(100), 8.0);
+        assert_close(annealing.temperature(150), 2.0);
+    }
+
+    #[test]
+    fn greedy_keeps_only_closer_candidates() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        assert!(Acceptance::Greedy.accept(&mut rng, 10.0, 9.0, 0));
+        assert!(!Acceptance::Greedy.accept(&mut rng, 10.0, 10.0, 0));
+        assert!(!Acceptance::Greedy.accept(&mut rng, 10.0, 11.0, 0));
+    }
+
+    #[test]
+    fn metropolis_keeps_equal_candidates_and_worse_ones_by_their_probability() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        // a constant temperature of 2
+        let acceptance = Acceptance::Annealing(Annealing { schedule: Schedule::Linear, start: 2.0, end: 2.0, epochs: 1 });
+        assert!((0..1000).all(|_| acceptance.accept(&mut rng, 10.0, 10.0, 0)), "delta 0 is always kept");
+
+        let trials = 20000;
+        let kept = (0..trials).filter(|_| acceptance.accept(&mut rng, 10.0, 11.0, 0)).count();
+        let expected = (-1.0f64 / 2.0).exp();
+        let rate = kept as f64 / trials as f64;
+        assert!((rate - expected).abs() < 0.02, "kept {rate} rather than {expected}");
+    }
+}
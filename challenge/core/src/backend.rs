@@ -0,0 +1,49 @@
+mod full;
+mod layered;
+
+use crate::fitness::Fitness;
+use crate::shape::Primitive;
+use crate::ImgRGBA;
+use serde::{Deserialize, Serialize};
+
+/// Backend selects how a candidate mutation is drawn and evaluated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    /// redraws every shape on a blank image and compares every pixel, as the baseline does
+    Full,
+    /// keeps the composition under each layer and the pixels covered by each shape,
+    /// so only the layers above the mutated shape and the pixels it touches are updated
+    Layered,
+}
+
+// Evaluator computes the distance between the target and the image drawn with
+// a candidate shape, and keeps whatever state it needs between epochs
+pub(crate) trait Evaluator: Send {
+    // reset rebuilds the state from scratch, with the shapes drawn on the canvas,
+    // and returns the distance of the shapes
+    fn reset(&mut self, target: &dyn Fitness, canvas: &ImgRGBA, shapes: &[Primitive]) -> f64;
+
+    // evaluate returns the distance of the shapes with shapes[index] replaced by the candidate
+    fn evaluate(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize, candidate: &Primitive) -> f64;
+
+    // commit keeps the last evaluated candidate, shapes[index] already holds it
+    fn commit(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize);
+
+    // evaluate_shapes returns the distance of other shapes, the same as the current
+    // ones below the index, after the stacking order or the number of shapes changed
+    fn evaluate_shapes(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize) -> f64;
+
+    // commit_shapes keeps the shapes last evaluated with evaluate_shapes
+    fn commit_shapes(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize);
+
+    // local_distances returns the local distance of each pixel of the current
+    // image, row by row, when the evaluator keeps them
+    fn local_distances(&self) -> Option<&[f64]>;
+}
+
+pub(crate) fn evaluator(backend: Backend) -> Box<dyn Evaluator> {
+    match backend {
+        Backend::Full => Box::<full::Full>::default(),
+        Backend::Layered => Box::<layered::Layered>::default(),
+    }
+}
@@ -0,0 +1,47 @@
+use super::Evaluator;
+use crate::draw::{draw, draw_shape};
+use crate::fitness::Fitness;
+use crate::shape::Primitive;
+use crate::ImgRGBA;
+
+// Full redraws every shape on a blank canvas for each candidate
+#[derive(Default)]
+pub(crate) struct Full {
+    canvas: ImgRGBA,
+    image: ImgRGBA,
+}
+
+impl Evaluator for Full {
+    fn reset(&mut self, target: &dyn Fitness, canvas: &ImgRGBA, shapes: &[Primitive]) -> f64 {
+        self.canvas.clone_from(canvas);
+        self.image.clone_from(canvas);
+        draw(&mut self.image, shapes);
+        target.distance(&self.image)
+    }
+
+    fn evaluate(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize, candidate: &Primitive) -> f64 {
+        // start from a blank canvas
+        self.image.clone_from(&self.canvas);
+
+        // draw the shapes with the mutated shape in place of the original
+        for (i, shape) in shapes.iter().enumerate() {
+            draw_shape(if i == index { candidate } else { shape }, &mut self.image);
+        }
+
+        target.distance(&self.image)
+    }
+
+    fn commit(&mut self, _target: &dyn Fitness, _shapes: &[Primitive], _index: usize) {}
+
+    fn evaluate_shapes(&mut self, target: &dyn Fitness, shapes: &[Primitive], _index: usize) -> f64 {
+        self.image.clone_from(&self.canvas);
+        draw(&mut self.image, shapes);
+        target.distance(&self.image)
+    }
+
+    fn commit_shapes(&mut self, _target: &dyn Fitness, _shapes: &[Primitive], _index: usize) {}
+
+    fn local_distances(&self) -> Option<&[f64]> {
+        None
+    }
+}
@@ -0,0 +1,190 @@
+use super::Evaluator;
+use crate::draw::{draw_pixels, draw_shape_recording};
+use crate::fitness::Fitness;
+use crate::shape::{Point, Primitive};
+use crate::ImgRGBA;
+
+// Layered only redraws what a mutation can change: the candidate is drawn on
+// top of the saved composition of the layers under it, the layers above it are
+// blended on the pixels they covered, and only the pixels of the old and the
+// new shape, or the ones the fitness looks at around them, get their local
+// distance to the target recomputed
+#[derive(Default)]
+pub(crate) struct Layered {
+    // composition of every layer for the last evaluated candidate
+    image: ImgRGBA,
+    // previous_image[i] is the composition of the layers below i
+    previous_image: Vec<ImgRGBA>,
+    // pixels covered by each shape
+    written_pixels: Vec<Vec<Point>>,
+    // pixels covered by the last evaluated candidate
+    dummy_layer: Vec<Point>,
+    // pixels covered by each shape from the lowest changed layer up, for the
+    // shapes last evaluated with evaluate_shapes
+    dummy_layers: Vec<Vec<Point>>,
+    // local distance to the target of each pixel of the best image, row by row
+    distance_matrix: Vec<f64>,
+    // distance_matrix updated with the pixels of the last evaluated candidate
+    candidate_matrix: Vec<f64>,
+}
+
+impl Evaluator for Layered {
+    fn reset(&mut self, target: &dyn Fitness, canvas: &ImgRGBA, shapes: &[Primitive]) -> f64 {
+        let (width, height) = target.dimensions();
+
+        // the shapes are drawn on a copy of the blank canvas
+        let mut image = canvas.clone();
+
+        self.previous_image = Vec::with_capacity(shapes.len());
+        self.written_pixels = vec![vec![]; shapes.len()];
+        for (shape, pixels) in shapes.iter().zip(self.written_pixels.iter_mut()) {
+            self.previous_image.push(image.clone());
+            draw_shape_recording(shape, &mut image, pixels);
+        }
+
+        self.image = image;
+        if target.reach().is_none() {
+            // the distance isn't made of local ones, so there is nothing to keep
+            self.distance_matrix.clear();
+            self.candidate_matrix.clear();
+            return target.distance(&self.image);
+        }
+
+        // get the initial distance for each pixel
+        self.distance_matrix = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| target.local_distance(&self.image, x, y))
+            .collect();
+        self.candidate_matrix = self.distance_matrix.clone();
+
+        average(&self.distance_matrix)
+    }
+
+    fn evaluate(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize, candidate: &Primitive) -> f64 {
+        // draw the candidate on top of the layers below it
+        self.image.clone_from(&self.previous_image[index]);
+        draw_shape_recording(candidate, &mut self.image, &mut self.dummy_layer);
+
+        // only the pixels written by each layer above need to be blended again
+        for (shape, pixels) in shapes.iter().zip(self.written_pixels.iter()).skip(index + 1) {
+            draw_pixels(shape, &mut self.image, pixels);
+        }
+
+        // only the pixels covered by the current best or the candidate may have changed
+        let changed = self.written_pixels[index].iter().chain(self.dummy_layer.iter());
+        update_distances(target, &self.image, &self.distance_matrix, &mut self.candidate_matrix, changed)
+    }
+
+    fn commit(&mut self, _target: &dyn Fitness, shapes: &[Primitive], index: usize) {
+        std::mem::swap(&mut self.distance_matrix, &mut self.candidate_matrix);
+        std::mem::swap(&mut self.written_pixels[index], &mut self.dummy_layer);
+
+        // save the new composition under each layer above the mutated one
+        let mut image = self.previous_image[index].clone();
+        draw_pixels(&shapes[index], &mut image, &self.written_pixels[index]);
+        let layers = self.previous_image.iter_mut().zip(shapes.iter().zip(self.written_pixels.iter()));
+        for (previous, (shape, pixels)) in layers.skip(index + 1) {
+            previous.clone_from(&image);
+            draw_pixels(shape, &mut image, pixels);
+        }
+    }
+
+    fn evaluate_shapes(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize) -> f64 {
+        // the layers from the index up are drawn again on the composition below them
+        self.image = self.composition_below(shapes, index);
+        self.dummy_layers.resize_with(shapes.len() - index, Vec::new);
+        for (shape, pixels) in shapes[index..].iter().zip(self.dummy_layers.iter_mut()) {
+            draw_shape_recording(shape, &mut self.image, pixels);
+        }
+
+        // only the pixels covered by the old or the new layers may have changed
+        let changed = self.written_pixels[index..].iter().chain(self.dummy_layers.iter()).flatten();
+        update_distances(target, &self.image, &self.distance_matrix, &mut self.candidate_matrix, changed)
+    }
+
+    fn commit_shapes(&mut self, _target: &dyn Fitness, shapes: &[Primitive], index: usize) {
+        std::mem::swap(&mut self.distance_matrix, &mut self.candidate_matrix);
+
+        // the layers from the index up are replaced, with the composition under each one
+        let mut image = self.composition_below(shapes, index);
+        self.previous_image.truncate(index);
+        self.written_pixels.truncate(index);
+        for (shape, pixels) in shapes[index..].iter().zip(self.dummy_layers.drain(..)) {
+            self.previous_image.push(image.clone());
+            draw_pixels(shape, &mut image, &pixels);
+            self.written_pixels.push(pixels);
+        }
+    }
+
+    fn local_distances(&self) -> Option<&[f64]> {
+        // the matrix is empty when the fitness has no local distances
+        if self.distance_matrix.is_empty() {
+            None
+        } else {
+            Some(&self.distance_matrix)
+        }
+    }
+}
+
+impl Layered {
+    // composition_below returns the composition of the layers below the index, which
+    // may be one past the last layer, shapes being the same as the current ones below it
+    fn composition_below(&self, shapes: &[Primitive], index: usize) -> ImgRGBA {
+        if index < self.previous_image.len() {
+            return self.previous_image[index].clone();
+        }
+        let mut image = self.previous_image[index - 1].clone();
+        draw_pixels(&shapes[index - 1], &mut image, &self.written_pixels[index - 1]);
+        image
+    }
+}
+
+// update_distances copies the local distances of the best image into candidate and
+// recomputes the ones of the image that the changed pixels can affect, then
+// returns the distance of the image
+fn update_distances<'a>(
+    target: &dyn Fitness,
+    image: &ImgRGBA,
+    best: &[f64],
+    candidate: &mut [f64],
+    changed: impl Iterator<Item = &'a Point>,
+) -> f64 {
+    let (width, height) = target.dimensions();
+    candidate.copy_from_slice(best);
+    match target.reach() {
+        Some(0) => {
+            for pixel in changed {
+                candidate[(pixel.y * width + pixel.x) as usize] = target.local_distance(image, pixel.x, pixel.y);
+            }
+        }
+        Some(reach) => {
+            // the local distances looking at a changed pixel are within reach of their box
+            let Some((xmin, ymin, xmax, ymax)) = bounds(changed) else {
+                return average(candidate);
+            };
+            let (xmin, ymin) = (xmin.saturating_sub(reach), ymin.saturating_sub(reach));
+            let (xmax, ymax) = ((xmax + reach).min(width - 1), (ymax + reach).min(height - 1));
+            for y in ymin..=ymax {
+                for x in xmin..=xmax {
+                    candidate[(y * width + x) as usize] = target.local_distance(image, x, y);
+                }
+            }
+        }
+        None => return target.distance(image),
+    }
+
+    average(candidate)
+}
+
+// average returns the mean distance of a distance matrix
+fn average(matrix: &[f64]) -> f64 {
+    matrix.iter().sum::<f64>() / matrix.len() as f64
+}
+
+// bounds returns the smallest box (xmin, ymin, xmax, ymax) holding the pixels, None without pixels
+fn bounds<'a>(pixels: impl Iterator<Item = &'a Point>) -> Option<(u32, u32, u32, u32)> {
+    pixels.fold(None, |bounds, pixel| match bounds {
+        None => Some((pixel.x, pixel.y, pixel.x, pixel.y)),
+        Some((xmin, ymin, xmax, ymax)) => Some((xmin.min(pixel.x), ymin.min(pixel.y), xmax.max(pixel.x), ymax.max(pixel.y))),
+    })
+}
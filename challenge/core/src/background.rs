@@ -0,0 +1,217 @@
+use crate::draw::blend_color;
+use crate::mutate::{mutate_channels, ColorMutation, Mutation};
+use crate::shape::{deserialize_color, serialize_color, BoundingBox};
+use crate::ImgRGBA;
+use image::Rgba;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Background selects the canvas the shapes are drawn on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Background {
+    /// opaque white, as the baseline does
+    #[default]
+    White,
+    /// fully transparent, shapes are composited with their alpha so the image
+    /// keeps transparency, and the alpha of the target counts in the distance
+    Transparent,
+    /// a fixed opaque color
+    Color(
+        #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+        Rgba<u8>
+    ),
+    /// the mean color of the target, which saves the shapes from covering a
+    /// white canvas first on dark images
+    Mean,
+    /// the mean color of each tile of the target cut in a grid of N by N tiles,
+    /// N being at most the smaller side of the target so every tile has a pixel
+    Tiles(u32),
+}
+
+impl Background {
+    /// canvas returns the colors of the background for a target, prepared with
+    /// target or not since it doesn't change the mean colors
+    pub fn canvas(self, target: &ImgRGBA) -> Canvas {
+        let (grid, colors) = match self {
+            Background::White => (1, vec![WHITE]),
+            Background::Transparent => (1, vec![Rgba([0, 0, 0, 0])]),
+            Background::Color(color) => (1, vec![Rgba([color[0], color[1], color[2], 255])]),
+            Background::Mean => (1, mean_colors(target, 1)),
+            Background::Tiles(grid) => {
+                let grid = grid.clamp(1, target.width().min(target.height()).max(1));
+                (grid, mean_colors(target, grid))
+            }
+        };
+        Canvas { grid, colors: colors.into_iter().map(|color| color.0).collect() }
+    }
+
+    /// target returns the target as the shapes have to approximate it: on an
+    /// opaque background the target is blended over the canvas, so transparent
+    /// pixels are compared with the canvas showing through rather than with
+    /// their hidden color, and need no shape to cover them
+    pub fn target(self, mut target: ImgRGBA) -> ImgRGBA {
+        if self == Background::Transparent {
+            return target;
+        }
+        let canvas = self.canvas(&target).paint(target.width(), target.height());
+        for (pixel, under) in target.pixels_mut().zip(canvas.pixels()) {
+            *pixel = blend_color(under, pixel);
+        }
+        target
+    }
+}
+
+/// Canvas holds the colors of a background, one for each tile of a grid of
+/// N by N tiles, row by row
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Canvas {
+    grid: u32,
+    colors: Vec<[u8; 4]>,
+}
+
+impl Canvas {
+    /// is_opaque tells whether every tile is opaque
+    pub fn is_opaque(&self) -> bool {
+        self.colors.iter().all(|color| color[3] == 255)
+    }
+
+    /// tiles returns the area and the color of each tile of an image of the given size
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<(BoundingBox, Rgba<u8>)> {
+        let mut tiles = Vec::with_capacity(self.colors.len());
+        for row in 0..self.grid {
+            for column in 0..self.grid {
+                let area = BoundingBox {
+                    xmin: (column * width / self.grid) as i32,
+                    ymin: (row * height / self.grid) as i32,
+                    xmax: ((column + 1) * width / self.grid) as i32,
+                    ymax: ((row + 1) * height / self.grid) as i32,
+                };
+                tiles.push((area, Rgba(self.colors[(row * self.grid + column) as usize])));
+            }
+        }
+        tiles
+    }
+
+    /// paint returns an image of the given size filled with the background
+    pub fn paint(&self, width: u32, height: u32) -> ImgRGBA {
+        if let [color] = self.colors[..] {
+            return ImgRGBA::from_pixel(width, height, Rgba(color));
+        }
+        ImgRGBA::from_fn(width, height, |x, y| {
+            Rgba(self.colors[(y * self.grid / height * self.grid + x * self.grid / width) as usize])
+        })
+    }
+
+    /// mutate returns a copy with the color of a random tile mutated as the color
+    /// of a shape, the colors staying opaque
+    pub fn mutate<R: RngCore>(&self, rng: &mut R, color_mutation: ColorMutation, mutation: Mutation, sigma: f64) -> Canvas {
+        let mut canvas = self.clone();
+        let tile = rng.gen_range(0..canvas.colors.len());
+        mutate_channels(rng, &mut canvas.colors[tile][..3], color_mutation, mutation, sigma);
+        canvas
+    }
+}
+
+// mean_colors returns the mean opaque color of each tile of the target cut in a
+// grid of N by N tiles, each pixel weighing as much as its alpha. Blending the
+// target over these colors keeps about the same means
+fn mean_colors(target: &ImgRGBA, grid: u32) -> Vec<Rgba<u8>> {
+    let (width, height) = target.dimensions();
+    let mut sums = vec![[0u64; 4]; (grid * grid) as usize];
+    for (x, y, pixel) in target.enumerate_pixels() {
+        let sum = &mut sums[(y * grid / height * grid + x * grid / width) as usize];
+        let alpha = pixel[3] as u64;
+        sum.iter_mut().zip(pixel.0).take(3).for_each(|(sum, channel)| *sum += channel as u64 * alpha);
+        sum[3] += alpha;
+    }
+
+    // tiles without an opaque pixel have no color to show, they are white
+    sums.iter()
+        .map(|sum| match sum[3] {
+            0 => WHITE,
+            count => Rgba([(sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8, 255]),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const RED: Rgba<u8> = Rgba([200, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 200, 255]);
+
+    // quarters returns a 4x4 image with a color in each quarter, row by row
+    fn quarters(colors: [Rgba<u8>; 4]) -> ImgRGBA {
+        ImgRGBA::from_fn(4, 4, |x, y| colors[(y / 2 * 2 + x / 2) as usize])
+    }
+
+    #[test]
+    fn tiles_take_the_mean_of_their_pixels() {
+        let target = quarters([RED, BLUE, BLUE, RED]);
+        let canvas = Background::Tiles(2).canvas(&target);
+        assert_eq!(canvas.colors, [RED.0, BLUE.0, BLUE.0, RED.0]);
+        assert_eq!(canvas.paint(4, 4), target);
+
+        // a single tile mixes them all
+        assert_eq!(Background::Mean.canvas(&target).colors, [[100, 0, 100, 255]]);
+    }
+
+    #[test]
+    fn tiles_cover_the_image() {
+        let canvas = Background::Tiles(3).canvas(&ImgRGBA::from_pixel(10, 7, RED));
+        let tiles = canvas.tiles(10, 7);
+        assert_eq!(tiles.len(), 9);
+        let area: i32 = tiles.iter().map(|(area, _)| (area.xmax - area.xmin) * (area.ymax - area.ymin)).sum();
+        assert_eq!(area, 10 * 7);
+        assert_eq!((tiles[8].0.xmax, tiles[8].0.ymax), (10, 7));
+    }
+
+    #[test]
+    fn tiles_are_at_most_one_per_pixel() {
+        let target = ImgRGBA::from_fn(6, 3, |x, _| if x % 2 == 0 { RED } else { BLUE });
+        let canvas = Background::Tiles(50).canvas(&target);
+        assert_eq!(canvas.grid, 3);
+        assert!(!canvas.colors.contains(&WHITE.0), "every tile has a pixel");
+    }
+
+    #[test]
+    fn means_ignore_the_color_hidden_under_transparency() {
+        let target = quarters([RED, Rgba([0, 255, 0, 0]), RED, RED]);
+        assert_eq!(Background::Mean.canvas(&target).colors, [RED.0]);
+        let canvas = Background::Tiles(2).canvas(&target);
+        assert_eq!(canvas.colors[1], WHITE.0, "a tile without an opaque pixel is white");
+    }
+
+    #[test]
+    fn target_is_blended_over_the_canvas() {
+        let target = quarters([RED, Rgba([0, 255, 0, 0]), Rgba([0, 0, 0, 128]), RED]);
+        let over_blue = Background::Color(BLUE).target(target.clone());
+        assert_eq!(over_blue.get_pixel(2, 0), &BLUE);
+        assert_eq!(over_blue.get_pixel(0, 2), &Rgba([0, 0, 99, 255]));
+        assert_eq!(over_blue.get_pixel(3, 3), &RED);
+
+        // a transparent pixel shows the canvas, so the shapes don't have to cover it
+        let mean = Background::Mean;
+        let prepared = mean.target(target.clone());
+        assert_eq!(prepared.get_pixel(2, 0).0, mean.canvas(&target).colors[0]);
+        assert_eq!(Background::Transparent.target(target.clone()), target);
+    }
+
+    #[test]
+    fn mutate_changes_one_tile_and_keeps_it_opaque() {
+        let canvas = Background::Tiles(2).canvas(&quarters([RED, BLUE, BLUE, RED]));
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for _ in 0..50 {
+            let mutated = canvas.mutate(&mut rng, ColorMutation::Replace, Mutation::Uniform, 0.1);
+            let changed = mutated.colors.iter().zip(&canvas.colors).filter(|(a, b)| a != b).count();
+            assert!(changed <= 1);
+            assert!(mutated.is_opaque());
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::background::Canvas;
+use crate::evolver::Config;
+use crate::mutate::StepSize;
+use crate::shape::Primitive;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Checkpoint is the state of a run, saved as JSON so a run can be paused,
+/// continued and shared. Resuming a checkpoint with the same target continues
+/// the run exactly where it stopped, random number generator included
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// parameters of the run
+    pub config: Config,
+    /// seed the random number generator was created with
+    pub seed: u64,
+    /// state of the random number generator of the first thread
+    pub rng: ChaCha8Rng,
+    /// state of the random number generators of the other threads
+    #[serde(default)]
+    pub worker_rngs: Vec<ChaCha8Rng>,
+    /// number of epochs run so far
+    pub epoch: u64,
+    /// epoch the target was last rescaled at, where the annealing schedule started over
+    #[serde(default)]
+    pub rescaled: u64,
+    /// size of the target image
+    pub width: u32,
+    pub height: u32,
+    /// distance between the best shapes and the target
+    pub best_distance: f64,
+    /// best shapes found so far, in stacking order
+    pub shapes: Vec<Primitive>,
+    /// shapes the run continues from, when simulated annealing moved away from the best ones
+    #[serde(default)]
+    pub current_shapes: Option<Vec<Primitive>>,
+    /// background of the best shapes
+    #[serde(default)]
+    pub background: Option<Canvas>,
+    /// background the run continues from, when simulated annealing moved away from the best one
+    #[serde(default)]
+    pub current_background: Option<Canvas>,
+    /// genomes of the genetic algorithm, from the closest to the target
+    #[serde(default)]
+    pub population: Vec<Vec<Primitive>>,
+    /// step size of Gaussian mutations
+    #[serde(default)]
+    pub step_size: Option<StepSize>,
+    /// epochs in a row without a better distance, when shapes grow
+    #[serde(default)]
+    pub stagnant: u64,
+}
+
+impl Checkpoint {
+    /// save writes the checkpoint to a file, replacing it at once so a run
+    /// stopped while saving never leaves a truncated checkpoint behind
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CheckpointError> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// load reads a checkpoint saved with save
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Checkpoint, CheckpointError> {
+        let bytes = fs::read(path)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+/// CheckpointError is returned when a checkpoint can't be saved, loaded or resumed
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    /// the checkpoint was made for a target of another size
+    TargetSize { expected: (u32, u32), found: (u32, u32) },
+    /// the checkpoint has no shapes
+    NoShapes,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(err) => write!(f, "{}", err),
+            CheckpointError::Format(err) => write!(f, "invalid checkpoint: {}", err),
+            CheckpointError::TargetSize { expected, found } => write!(
+                f,
+                "the checkpoint was made for a {}x{} target, not {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            CheckpointError::NoShapes => write!(f, "the checkpoint has no shapes"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CheckpointError::Io(err) => Some(err),
+            CheckpointError::Format(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(err: io::Error) -> Self {
+        CheckpointError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(err: serde_json::Error) -> Self {
+        CheckpointError::Format(err)
+    }
+}
@@ -0,0 +1,681 @@
+//! Command line front-end shared by the `evolve` and `upgrade` binaries.
+
+mod dashboard;
+
+use crate::{Acceptance, Annealing, Background, Checkpoint, Config, Crossover, Evolver, Genetic, Growth, ImgRGBA, LogFormat, Metric, Mutation, Objective, Operator, Operators, ProgressLog, Record, Schedule, Selection, ShapeKind, Stop, Structure, Timelapse, Weights};
+use clap::Args;
+use dashboard::Dashboard;
+use image::Rgba;
+use image::imageops::{self, FilterType};
+use std::cmp::max;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// number of epochs of a run without any other stop condition
+const DEFAULT_EPOCHS: u32 = 10000;
+// number of the best genomes a generation keeps unless told otherwise
+const DEFAULT_ELITISM: u32 = 2;
+
+/// EvolveArgs holds the options of the run subcommand that don't depend on the binary
+#[derive(Args, Debug)]
+pub struct EvolveArgs {
+    /// Number of shapes drawn in the image
+    #[arg(short = 'n', long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "resume")]
+    pub count: u32,
+
+    /// Start with N shapes and add one where the image is farthest from the target whenever the best distance stops improving, up to --count shapes
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["resume", "population"])]
+    pub grow: Option<u32>,
+
+    /// Number of epochs in a row without a better distance after which a shape is added
+    #[arg(long, value_name = "N", default_value_t = 200, value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "resume")]
+    pub grow_patience: u64,
+
+    /// Kinds of shape drawn, separated by commas, each shape is given one of them at random
+    #[arg(short, long, value_delimiter = ',', default_value = "triangle", conflicts_with = "resume")]
+    pub shapes: Vec<ShapeKind>,
+
+    /// Number of vertices of the polygons
+    #[arg(short = 'V', long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(3..), conflicts_with = "resume")]
+    pub vertices: u32,
+
+    /// Draw polygons as the convex hull of their vertices instead of joining them in order
+    #[arg(long, conflicts_with = "resume")]
+    pub convex: bool,
+
+    /// How the distance between the colors of the image and the target is measured
+    #[arg(long, value_enum, default_value_t = Metric::Rgb, conflicts_with = "resume")]
+    pub metric: Metric,
+
+    /// What the distance to the target measures: color differences per pixel, the structural similarity of 7x7 windows (ssim), the structural similarity over a pyramid of halved images (ms-ssim), or color differences weighted by the edges of the target (edges)
+    #[arg(long, value_enum, default_value_t = Objective::Color, conflicts_with = "resume")]
+    pub objective: Objective,
+
+    /// How much each pixel counts in the distance: the brightness of a grayscale mask image, resized to the target, or `saliency` to weigh the detailed areas of the target more
+    #[arg(long, value_name = "PATH|saliency", conflicts_with = "resume")]
+    pub weights: Option<String>,
+
+    /// Canvas the shapes are drawn on: `white`, `transparent` to keep the transparency of the target in the output, a color as `#rrggbb`, the mean color of the target (`mean`), or the mean color of each tile of the target cut in N by N tiles (`tiles:N`, N being lowered to the smaller side of the target in pixels)
+    #[arg(long, value_name = "BACKGROUND", default_value = "white", value_parser = background, conflicts_with = "resume")]
+    pub background: Background,
+
+    /// Also mutate the colors of the background, as often as each shape, with the hill climber
+    #[arg(long, conflicts_with_all = ["resume", "population"])]
+    pub evolve_background: bool,
+
+    /// How mutations change coordinates and colors: replace them at random, or nudge them by Gaussian steps adapted with the 1/5th success rule
+    #[arg(short, long, value_enum, default_value_t = Mutation::Uniform, conflicts_with = "resume")]
+    pub mutation: Mutation,
+
+    /// Initial size of the Gaussian steps, as a fraction of the largest side of the image and of the color range
+    #[arg(long, default_value_t = 0.05, value_parser = positive, conflicts_with = "resume")]
+    pub sigma: f64,
+
+    /// Probability that an epoch trades the places of 2 shapes in the stacking order instead of mutating a shape
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = probability, conflicts_with_all = ["resume", "population"])]
+    pub swap: f64,
+
+    /// Probability that an epoch moves a shape on top of the others
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = probability, conflicts_with_all = ["resume", "population"])]
+    pub to_top: f64,
+
+    /// Probability that an epoch moves a shape under the others
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = probability, conflicts_with_all = ["resume", "population"])]
+    pub to_bottom: f64,
+
+    /// Probability that an epoch removes a shape, keeping at least one
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = probability, conflicts_with_all = ["resume", "population"])]
+    pub delete: f64,
+
+    /// Probability that an epoch adds a random shape at a random place in the stacking order, up to --count shapes
+    #[arg(long, value_name = "P", default_value_t = 0.0, value_parser = probability, conflicts_with_all = ["resume", "population"])]
+    pub insert: f64,
+
+    /// Weights of the operators mutating a shape, as NAME=WEIGHT separated by commas, the operators left out weighing 0: vertex, move (the whole shape to a random place), channel, color (all 4 channels), alpha, translate, scale and rotate [default: vertex=1,channel=1 for upgrade, vertex=1,color=1 for evolve]
+    #[arg(long, value_name = "NAME=WEIGHT", value_delimiter = ',', value_parser = operator_weight, conflicts_with = "resume")]
+    pub operators: Vec<(Operator, u32)>,
+
+    /// Use simulated annealing with this temperature schedule, which sometimes keeps worse mutations to escape local optima
+    #[arg(long, value_enum, value_name = "SCHEDULE", conflicts_with = "resume")]
+    pub anneal: Option<Schedule>,
+
+    /// Temperature of simulated annealing at the start, in units of distance
+    #[arg(long, default_value_t = 0.002, value_parser = positive, conflicts_with = "resume")]
+    pub temperature: f64,
+
+    /// Temperature of simulated annealing at the end of the schedule
+    #[arg(long, default_value_t = 0.00002, value_parser = positive, conflicts_with = "resume")]
+    pub final_temperature: f64,
+
+    /// Number of epochs the temperature takes to cool down, a cycle with the reheating schedule, starting over at each size of the pyramid [default: --epochs, or 10000]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "resume")]
+    pub anneal_epochs: Option<u64>,
+
+    /// Evolve a population of this many images with a genetic algorithm, each epoch being a generation
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(2..), conflicts_with = "resume")]
+    pub population: Option<u32>,
+
+    /// How the genetic algorithm picks parents
+    #[arg(long, value_enum, default_value_t = Selection::Tournament, requires = "population")]
+    pub selection: Selection,
+
+    /// Number of images competing in each tournament
+    #[arg(long, value_name = "N", default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..), requires = "population")]
+    pub tournament: u32,
+
+    /// How the shapes of 2 parents are spliced into a child
+    #[arg(long, value_enum, default_value_t = Crossover::OnePoint, requires = "population")]
+    pub crossover: Crossover,
+
+    /// Probability that a child is spliced from 2 parents rather than copied from one
+    #[arg(long, default_value_t = 0.9, value_parser = probability, requires = "population")]
+    pub crossover_rate: f64,
+
+    /// Number of the best images kept unchanged in the next generation, less than --population [default: 2, or 1 with a population of 2]
+    #[arg(long, value_name = "N", requires = "population")]
+    pub elitism: Option<u32>,
+
+    /// Number of mutations to try, on top of the ones of a resumed run [default: 10000 without --time, --distance or --stagnation]
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub epochs: Option<u32>,
+
+    /// Stop after this much time, such as 90s, 10m or 1h30m, a plain number being seconds
+    #[arg(long, value_name = "DURATION", value_parser = duration)]
+    pub time: Option<Duration>,
+
+    /// Stop once the best distance is at most this one
+    #[arg(long, value_parser = positive)]
+    pub distance: Option<f64>,
+
+    /// Stop after N epochs in a row without getting closer to the target
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub stagnation: Option<u64>,
+
+    /// Print the current distance every N epochs [default: 1% of the epochs, or 100]
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub log_every: Option<u32>,
+
+    /// Print at most one line every this many seconds, skipping the log lines in between
+    #[arg(long, value_name = "SECONDS", default_value_t = 1.0, value_parser = positive)]
+    pub log_interval: f64,
+
+    /// Show a dashboard updated in place (progress, ETA, epochs per second, distances, acceptance per mutation kind and a sparkline of the best distance) instead of printing log lines
+    #[arg(long)]
+    pub dashboard: bool,
+
+    /// Save the epoch, best distance, acceptance, mutation kind, shape index and elapsed time of every epoch to this file, as CSV or JSON Lines depending on the extension (.csv, .jsonl or .ndjson)
+    #[arg(long, value_name = "PATH", value_parser = log_path)]
+    pub log: Option<String>,
+
+    /// Seed of the random number generator, runs with the same seed and options produce the same image [default: random]
+    #[arg(long, conflicts_with = "resume")]
+    pub seed: Option<u64>,
+
+    /// Evolve on the target halved N - 1 times first, then on each size up to the target, as the first epochs are cheaper on smaller images
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=8), conflicts_with = "resume")]
+    pub pyramid: u32,
+
+    /// Number of epochs run on each reduced size of the pyramid, from the smallest, separated by commas, the last one going for the remaining sizes. They come before --epochs and count against the other stop conditions
+    #[arg(long, value_name = "EPOCHS", value_delimiter = ',', default_value = "1000", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "resume")]
+    pub level_epochs: Vec<u64>,
+
+    /// Number of candidates evaluated in parallel each epoch, one per thread, or of threads sharing the genomes of a generation, 0 uses every core, at most 4 times the number of cores
+    #[arg(short = 'j', long, default_value_t = 1, value_parser = threads, conflicts_with = "resume")]
+    pub threads: usize,
+
+    /// Also save the shapes as an SVG document
+    #[arg(long, value_name = "PATH")]
+    pub svg: Option<String>,
+
+    /// Save a frame of the run as a PNG image in this directory every --frame-every epochs or accepted mutations
+    #[arg(long, value_name = "DIR")]
+    pub frames: Option<String>,
+
+    /// Also assemble the frames into an animation, a GIF or an animated PNG depending on the extension (.gif, .png or .apng), of at most 257 frames, every other one being dropped when there are more
+    #[arg(long, value_name = "PATH", value_parser = animation)]
+    pub timelapse: Option<String>,
+
+    /// Number of epochs or accepted mutations between 2 frames
+    #[arg(long, value_name = "N", default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    pub frame_every: u32,
+
+    /// Whether --frame-every counts epochs or accepted mutations
+    #[arg(long, value_enum, default_value_t = FrameOn::Epochs)]
+    pub frame_on: FrameOn,
+
+    /// Time each frame of the animation is shown, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 100)]
+    pub frame_delay: u16,
+
+    /// Save the state of the run to this file, at the end and every --checkpoint-every epochs [default: the --resume file]
+    #[arg(long, value_name = "PATH")]
+    pub checkpoint: Option<String>,
+
+    /// Save a checkpoint every N epochs
+    #[arg(long, value_name = "N", default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+    pub checkpoint_every: u32,
+
+    /// Continue the run saved in this checkpoint, with the options it was started with
+    #[arg(long, value_name = "PATH")]
+    pub resume: Option<String>,
+}
+
+/// FrameOn selects what --frame-every counts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum FrameOn {
+    /// a frame every N epochs, so the animation follows the time spent
+    Epochs,
+    /// a frame every N accepted mutations, so the animation follows the changes of the image
+    Accepts,
+}
+
+impl EvolveArgs {
+    /// stop returns the conditions that end the run, the first one met ending it
+    pub fn stop(&self) -> Stop {
+        let epochs = match (self.epochs, self.time, self.distance, self.stagnation) {
+            (None, None, None, None) => Some(DEFAULT_EPOCHS),
+            (epochs, ..) => epochs,
+        };
+        Stop { epochs: epochs.map(u64::from), time: self.time, distance: self.distance, stagnation: self.stagnation }
+    }
+
+    /// apply overrides the parameters of the config set on the command line
+    pub fn apply(&self, config: &mut Config) {
+        config.shapes = self.count as usize;
+        config.kinds = self.shapes.clone();
+        config.vertices = self.vertices as usize;
+        config.convex = self.convex;
+        config.metric = self.metric;
+        config.objective = self.objective;
+        config.background = self.background;
+        config.evolve_background = self.evolve_background;
+        config.mutation = self.mutation;
+        config.sigma = self.sigma;
+        if !self.operators.is_empty() {
+            let mut operators = Operators::default();
+            for &(operator, weight) in &self.operators {
+                *operators.weight_mut(operator) = weight;
+            }
+            config.operators = Some(operators);
+        }
+        config.structure = Structure {
+            swap: self.swap,
+            to_top: self.to_top,
+            to_bottom: self.to_bottom,
+            delete: self.delete,
+            insert: self.insert,
+        };
+        config.acceptance = match self.anneal {
+            None => Acceptance::Greedy,
+            Some(schedule) => Acceptance::Annealing(Annealing {
+                schedule,
+                start: self.temperature,
+                end: self.final_temperature,
+                epochs: self.anneal_epochs.or(self.stop().epochs).unwrap_or(DEFAULT_EPOCHS as u64),
+            }),
+        };
+        config.genetic = self.population.map(|population| Genetic {
+            population: population as usize,
+            selection: self.selection,
+            tournament: self.tournament as usize,
+            crossover: self.crossover,
+            crossover_rate: self.crossover_rate,
+            // the default leaves room for a child in the smallest population
+            elitism: self.elitism.unwrap_or(DEFAULT_ELITISM.min(population - 1)) as usize,
+        });
+        config.growth = self.grow.map(|start| Growth { start: start as usize, patience: self.grow_patience });
+        config.seed = self.seed;
+        config.threads = match self.threads {
+            0 => cores(),
+            n => n,
+        };
+    }
+}
+
+/// run evolves the shapes towards the target image and saves the output image
+pub fn run(target: &str, output: &str, args: &EvolveArgs, mut config: Config) -> Result<(), Box<dyn Error>> {
+    let begin = Instant::now();
+
+    args.apply(&mut config);
+
+    // opens a reference image for the fitness func
+    let ref_image = image::open(target)
+        .map_err(|err| format!("cannot open target image {}: {}", target, err))?
+        .into_rgba8();
+    let size = ref_image.dimensions();
+
+    if args.dashboard && !io::stdout().is_terminal() {
+        return Err("--dashboard needs a terminal".into());
+    }
+
+    if args.grow.is_some_and(|start| start > args.count) {
+        return Err("--grow must be at most --count".into());
+    }
+
+    if config.shape_operators().total() == 0 {
+        return Err("--operators needs an operator with a weight".into());
+    }
+
+    // the elites would fill the whole generation and no child would be bred
+    if args.population.zip(args.elitism).is_some_and(|(population, elitism)| elitism >= population) {
+        return Err("--elitism must be less than --population".into());
+    }
+
+    if config.structure.total() > 1.0 {
+        return Err("--swap, --to-top, --to-bottom, --delete and --insert must add up to at most 1".into());
+    }
+
+    if args.evolve_background && config.background == Background::Transparent {
+        return Err("--evolve-background needs an opaque --background".into());
+    }
+
+    match args.weights.as_deref() {
+        None => {}
+        Some(_) if config.objective == Objective::MsSsim => return Err("--weights doesn't apply to --objective ms-ssim".into()),
+        Some("saliency") => config.weights = Weights::Saliency,
+        Some(path) => {
+            let mask = image::open(path)
+                .map_err(|err| format!("cannot open weights mask {}: {}", path, err))?
+                .into_luma8();
+            let (width, height) = ref_image.dimensions();
+            config.weights = Weights::Mask(imageops::resize(&mask, width, height, FilterType::Triangle).into_raw());
+        }
+    }
+
+    let (mut evolver, mut levels) = match &args.resume {
+        Some(path) => {
+            let checkpoint = Checkpoint::load(path)
+                .map_err(|err| format!("cannot load checkpoint {}: {}", path, err))?;
+            let evolver = Evolver::resume(ref_image, checkpoint)
+                .map_err(|err| format!("cannot resume checkpoint {}: {}", path, err))?;
+            println!("Resuming from epoch {} with distance {:.3}", evolver.epoch(), evolver.best_distance());
+            (evolver, VecDeque::new())
+        }
+        None if args.pyramid > 1 => pyramid(ref_image, config, args),
+        None => (Evolver::new(ref_image, config), VecDeque::new()),
+    };
+
+    // --epochs are run at the size of the target, after the epochs of the pyramid
+    let mut stop = args.stop();
+    stop.epochs = stop.epochs.map(|epochs| epochs + levels.iter().map(|(epochs, _)| epochs).sum::<u64>());
+    let log_every = args.log_every.map_or(stop.epochs.map_or(100, |epochs| max(epochs / 100, 1)), u64::from);
+    println!("Seed {} - metric {} - objective {}", evolver.seed(), evolver.config().metric, evolver.config().objective);
+
+    let checkpoint_path = args.checkpoint.as_ref().or(args.resume.as_ref());
+    let save_checkpoint = |evolver: &Evolver| -> Result<(), String> {
+        match checkpoint_path {
+            Some(path) => evolver.checkpoint().save(path)
+                .map_err(|err| format!("cannot save checkpoint {}: {}", path, err)),
+            None => Ok(()),
+        }
+    };
+
+    // frames are taken from the start, then every --frame-every epochs or accepts
+    let mut timelapse = Timelapse::new(args.frame_delay);
+    if let Some(dir) = &args.frames {
+        fs::create_dir_all(dir).map_err(|err| format!("cannot create frames directory {}: {}", dir, err))?;
+    }
+    save_frame(&evolver, size, args, &mut timelapse)?;
+    let mut since_frame = 0u32;
+    // epochs run at the current size of the pyramid
+    let mut level_done = 0u64;
+
+    // a resumed run appends its records to the log of the run it continues
+    let mut log = match &args.log {
+        Some(path) => Some(ProgressLog::create(path, args.resume.is_some())
+            .map_err(|err| format!("cannot open log {}: {}", path, err))?),
+        None => None,
+    };
+
+    let mut duration = Duration::ZERO;
+    // mutations tried and kept since the last log line
+    let (mut tried, mut kept) = (0u32, 0u32);
+    let log_interval = Duration::from_secs_f64(args.log_interval);
+    let mut printed: Option<Instant> = None;
+    let mut dashboard = match args.dashboard {
+        true => Some(Dashboard::new(&evolver).map_err(|err| format!("cannot show dashboard: {}", err))?),
+        false => None,
+    };
+
+    // main loop, runs mutation, gets fitness (distance between 2 images), keeps or discards a mutation
+    // until one of the stop conditions is met
+    let mut watch = stop.watch(&evolver);
+    let reason = loop {
+        let i = watch.done();
+        let now = Instant::now();
+        let step = evolver.step();
+        duration += now.elapsed();
+        let reason = watch.check(&evolver);
+        tried += 1;
+        kept += step.accepted as u32;
+
+        if let Some(log) = &mut log {
+            log.write(&Record::new(&step, evolver.best_distance(), begin.elapsed()))
+                .map_err(|err| format!("cannot write log: {}", err))?;
+        }
+
+        if let Some(dashboard) = &mut dashboard {
+            dashboard.update(&step, &evolver, &watch, duration).map_err(|err| format!("cannot show dashboard: {}", err))?;
+        } else if i.is_multiple_of(log_every) && printed.is_none_or(|printed| printed.elapsed() >= log_interval) {
+            let mut line = format!(
+                "Mutation #{} - current distance: {:.3} - rate {:.3} - accepted {:.1}%",
+                step.epoch, evolver.distance(), (i + 1) as f64 / duration.as_secs_f64(), 100.0 * kept as f64 / tried as f64,
+            );
+            if evolver.config().mutation == Mutation::Gaussian {
+                line += &format!(" - sigma {:.4}", evolver.sigma());
+            }
+            if evolver.config().growth.is_some() || !evolver.config().structure.is_none() {
+                line += &format!(" - shapes {}", evolver.shapes().len());
+            }
+            if let Acceptance::Annealing(_) = evolver.config().acceptance {
+                line += &format!(" - temperature {:.5} - best distance {:.3}", evolver.temperature(), evolver.best_distance());
+            }
+            println!("{}", line);
+            (tried, kept) = (0, 0);
+            printed = Some(Instant::now());
+        }
+        if let Some(&(epochs, _)) = levels.front() {
+            level_done += 1;
+            // a run stopped during the pyramid still ends at the size of the target
+            if level_done == epochs || reason.is_some() {
+                let (width, height) = evolver.target().dimensions();
+                println!(
+                    "Level {}/{} at {}x{} - {} epochs - best distance {:.3}",
+                    args.pyramid as usize - levels.len(), args.pyramid, width, height, level_done, evolver.best_distance(),
+                );
+                let next = if reason.is_some() { levels.drain(..).next_back() } else { levels.pop_front() };
+                evolver.rescale(next.unwrap().1);
+                watch.rescaled(&evolver);
+                level_done = 0;
+            }
+        }
+        since_frame += match args.frame_on {
+            FrameOn::Epochs => 1,
+            FrameOn::Accepts => step.accepted as u32,
+        };
+        if since_frame >= args.frame_every {
+            save_frame(&evolver, size, args, &mut timelapse)?;
+            since_frame = 0;
+        }
+        if (i + 1).is_multiple_of(args.checkpoint_every as u64) {
+            save_checkpoint(&evolver)?;
+        }
+        if let Some(reason) = reason {
+            break reason;
+        }
+    };
+    if let Some(dashboard) = dashboard {
+        dashboard.finish(&evolver, &watch, duration).map_err(|err| format!("cannot show dashboard: {}", err))?;
+    }
+    save_checkpoint(&evolver)?;
+    if let Some(log) = &mut log {
+        log.flush().map_err(|err| format!("cannot write log: {}", err))?;
+    }
+    if since_frame > 0 {
+        save_frame(&evolver, size, args, &mut timelapse)?;
+    }
+    if let Some(path) = &args.timelapse {
+        timelapse.save(path).map_err(|err| format!("cannot save timelapse {}: {}", path, err))?;
+    }
+
+    let epochs = watch.done();
+    println!("Stopped: {}", reason);
+    println!("Computational time for {} epochs: {:.3} seconds with rate of {:.3} epoch/second", epochs, duration.as_secs_f64(), epochs as f64 / duration.as_secs_f64());
+    let stats = evolver.operator_stats();
+    for operator in Operator::ALL.into_iter().filter(|&operator| stats.tried(operator) > 0) {
+        println!(
+            "Operator {}: {} tried, {} kept ({:.1}%)",
+            operator, stats.tried(operator), stats.kept(operator), 100.0 * stats.kept(operator) as f64 / stats.tried(operator) as f64,
+        );
+    }
+
+    evolver.render().save(output)
+        .map_err(|err| format!("cannot save output image {}: {}", output, err))?;
+
+    if let Some(path) = &args.svg {
+        fs::write(path, evolver.render_svg())
+            .map_err(|err| format!("cannot save svg {}: {}", path, err))?;
+    }
+
+    println!("Best fitness {}", evolver.best_distance());
+    println!("Total running time {:.3} seconds", begin.elapsed().as_secs_f64());
+
+    Ok(())
+}
+
+// pyramid returns an evolver on the target halved --pyramid - 1 times, with the
+// levels of the run: the --level-epochs of each size, from the smallest, and
+// the target to rescale to after them, the last one being the target itself
+fn pyramid(target: ImgRGBA, mut config: Config, args: &EvolveArgs) -> (Evolver, VecDeque<(u64, ImgRGBA)>) {
+    let (width, height) = target.dimensions();
+    let levels = args.pyramid;
+    let size = |level: u32| {
+        let halvings = levels - 1 - level;
+        ((width >> halvings).max(1), (height >> halvings).max(1))
+    };
+    let resized = |level: u32| {
+        let (level_width, level_height) = size(level);
+        imageops::resize(&target, level_width, level_height, FilterType::Triangle)
+    };
+
+    config.weights = config.weights.resize((width, height), size(0));
+    let evolver = Evolver::new(resized(0), config);
+    let sizes = (0..levels - 1)
+        .map(|level| {
+            let epochs = args.level_epochs[(level as usize).min(args.level_epochs.len() - 1)];
+            (epochs, if level + 2 == levels { target.clone() } else { resized(level + 1) })
+        })
+        .collect();
+    (evolver, sizes)
+}
+
+// save_frame saves the image of the best shapes in the frames directory and
+// adds it to the timelapse, when they were asked for. The frames of the pyramid
+// are stretched to the size of the target, as every frame of a timelapse
+fn save_frame(evolver: &Evolver, (width, height): (u32, u32), args: &EvolveArgs, timelapse: &mut Timelapse) -> Result<(), String> {
+    if args.frames.is_none() && args.timelapse.is_none() {
+        return Ok(());
+    }
+
+    let mut frame = evolver.render();
+    if frame.dimensions() != (width, height) {
+        frame = imageops::resize(&frame, width, height, FilterType::Nearest);
+    }
+    if let Some(dir) = &args.frames {
+        let path = Path::new(dir).join(format!("frame-{:08}.png", evolver.epoch()));
+        frame.save(&path).map_err(|err| format!("cannot save frame {}: {}", path.display(), err))?;
+    }
+    if args.timelapse.is_some() {
+        timelapse.push(frame);
+    }
+    Ok(())
+}
+
+// animation parses the path of an animation, whose extension is gif, png or apng
+fn animation(value: &str) -> Result<String, String> {
+    let extension = Path::new(value).extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "gif" | "png" | "apng" => Ok(value.to_string()),
+        _ => Err("must end with .gif, .png or .apng".to_string()),
+    }
+}
+
+// log_path parses the path of a progress log, whose extension is csv, jsonl or ndjson
+fn log_path(value: &str) -> Result<String, String> {
+    match LogFormat::from_path(value) {
+        Some(_) => Ok(value.to_string()),
+        None => Err("must end with .csv, .jsonl or .ndjson".to_string()),
+    }
+}
+
+// duration parses a time such as 90s, 10m, 1h30m or 2.5h, a plain number being seconds
+fn duration(value: &str) -> Result<Duration, String> {
+    let invalid = || "must be a time such as 90s, 10m or 1h30m".to_string();
+    let mut seconds = value.parse::<f64>().unwrap_or(0.0);
+    let mut rest = if seconds > 0.0 { "" } else { value };
+    while !rest.is_empty() {
+        let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.').ok_or_else(invalid)?;
+        let number = rest[..end].parse::<f64>().map_err(|_| invalid())?;
+        let unit = rest[end..].chars().next().ok_or_else(invalid)?;
+        seconds += number * match unit {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return Err(invalid()),
+        };
+        rest = &rest[end + 1..];
+    }
+    match seconds > 0.0 {
+        true => Duration::try_from_secs_f64(seconds).map_err(|_| invalid()),
+        false => Err(invalid()),
+    }
+}
+
+// positive parses a finite number greater than 0
+fn positive(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if number > 0.0 && number.is_finite() => Ok(number),
+        Ok(_) => Err("must be a finite number greater than 0".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// threads parses a number of threads up to 4 times the number of cores, since each
+// thread keeps its own copy of the layers and more of them only wait for a core
+fn threads(value: &str) -> Result<usize, String> {
+    let max = 4 * cores();
+    match value.parse::<usize>() {
+        Ok(threads) if threads <= max => Ok(threads),
+        Ok(_) => Err(format!("must be at most {} (4 times the number of cores)", max)),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// cores returns the number of threads the machine runs in parallel
+fn cores() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// probability parses a number from 0 to 1
+fn probability(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if (0.0..=1.0).contains(&number) => Ok(number),
+        Ok(_) => Err("must be between 0 and 1".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// operator_weight parses an operator and its weight as NAME=WEIGHT
+fn operator_weight(value: &str) -> Result<(Operator, u32), String> {
+    let (name, weight) = value.split_once('=').ok_or("must be NAME=WEIGHT")?;
+    let operator = <Operator as clap::ValueEnum>::from_str(name, true)
+        .map_err(|_| format!("unknown operator {}, must be vertex, move, channel, color, alpha, translate, scale or rotate", name))?;
+    let weight = weight.parse::<u32>().map_err(|err| format!("invalid weight {}: {}", weight, err))?;
+    Ok((operator, weight))
+}
+
+// background parses white, transparent, mean, tiles:N or a #rrggbb color
+fn background(value: &str) -> Result<Background, String> {
+    match value {
+        "white" => Ok(Background::White),
+        "transparent" => Ok(Background::Transparent),
+        "mean" => Ok(Background::Mean),
+        _ => {
+            if let Some(tiles) = value.strip_prefix("tiles:") {
+                return match tiles.parse::<u32>() {
+                    Ok(tiles) if tiles > 0 => Ok(Background::Tiles(tiles)),
+                    _ => Err("tiles:N needs a number of tiles greater than 0".to_string()),
+                };
+            }
+            let hex = value.strip_prefix('#').unwrap_or(value);
+            match u32::from_str_radix(hex, 16) {
+                Ok(rgb) if hex.len() == 6 => Ok(Background::Color(Rgba([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255]))),
+                _ => Err("must be white, transparent, mean, tiles:N or a #rrggbb color".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_reads_units() {
+        for (value, seconds) in [("90s", 90.0), ("2m", 120.0), ("1h30m", 5400.0), ("2.5h", 9000.0), ("1m30s", 90.0), ("90", 90.0), ("0.5", 0.5)] {
+            assert_eq!(duration(value), Ok(Duration::from_secs_f64(seconds)), "{}", value);
+        }
+    }
+
+    #[test]
+    fn duration_rejects_nothing_and_unknown_units() {
+        for value in ["0", "0s", "", "10x", "5d", "m", "5m3", "-5s", "1.2.3s"] {
+            assert!(duration(value).is_err(), "{}", value);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{Evolver, MutationKind, Step, Watch};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+use ratatui::{Terminal, TerminalOptions, Viewport};
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+
+// lines of the terminal taken by the dashboard
+const HEIGHT: u16 = 9;
+// time between 2 redraws of the dashboard
+const REFRESH: Duration = Duration::from_millis(100);
+// best distances kept for the sparkline, every other one is dropped when full
+const HISTORY: usize = 512;
+
+// every kind of mutation, in the order of their declaration so a kind indexes its counts
+const KINDS: [MutationKind; 9] = [
+    MutationKind::Geometry,
+    MutationKind::Color,
+    MutationKind::Background,
+    MutationKind::Generation,
+    MutationKind::Swap,
+    MutationKind::ToTop,
+    MutationKind::ToBottom,
+    MutationKind::Delete,
+    MutationKind::Insert,
+];
+
+// Dashboard shows the progress of a run in place, below the lines printed before it
+pub(super) struct Dashboard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    // mutations tried and kept, for each kind
+    tried: [u64; KINDS.len()],
+    kept: [u64; KINDS.len()],
+    // best distance every `sample_every` epochs, the first one being the distance at the start
+    history: Vec<f64>,
+    sample_every: u64,
+    drawn: Option<Instant>,
+}
+
+impl Dashboard {
+    // new makes room for the dashboard of a run
+    pub(super) fn new(evolver: &Evolver) -> io::Result<Dashboard> {
+        let options = TerminalOptions { viewport: Viewport::Inline(HEIGHT) };
+        let terminal = Terminal::with_options(CrosstermBackend::new(io::stdout()), options)?;
+        Ok(Dashboard {
+            terminal,
+            tried: [0; KINDS.len()],
+            kept: [0; KINDS.len()],
+            history: vec![evolver.best_distance()],
+            sample_every: 1,
+            drawn: None,
+        })
+    }
+
+    // update counts a step and redraws the dashboard when it is due, duration being
+    // the time spent in the epochs so far
+    pub(super) fn update(&mut self, step: &Step, evolver: &Evolver, watch: &Watch, duration: Duration) -> io::Result<()> {
+        let kind = step.kind as usize;
+        self.tried[kind] += 1;
+        self.kept[kind] += step.accepted as u64;
+
+        if watch.done().is_multiple_of(self.sample_every) {
+            self.history.push(evolver.best_distance());
+            if self.history.len() == HISTORY {
+                self.history = self.history.iter().step_by(2).cloned().collect();
+                self.sample_every *= 2;
+            }
+        }
+
+        if self.drawn.is_none_or(|drawn| drawn.elapsed() >= REFRESH) {
+            self.draw(evolver, watch, duration)?;
+        }
+        Ok(())
+    }
+
+    // finish draws the dashboard a last time and leaves it above the lines printed after it
+    pub(super) fn finish(mut self, evolver: &Evolver, watch: &Watch, duration: Duration) -> io::Result<()> {
+        self.draw(evolver, watch, duration)?;
+        let area = self.terminal.get_frame().area();
+        self.terminal.set_cursor_position(Position { x: 0, y: area.bottom() - 1 })?;
+        self.terminal.show_cursor()?;
+        println!();
+        Ok(())
+    }
+
+    fn draw(&mut self, evolver: &Evolver, watch: &Watch, duration: Duration) -> io::Result<()> {
+        self.drawn = Some(Instant::now());
+
+        // the time left follows the epochs or the time limit, a run stopped by
+        // its distance or by stagnation has no end to foresee
+        let rate = watch.done() as f64 / duration.as_secs_f64().max(f64::EPSILON);
+        let ratio = watch.progress().unwrap_or(0.0);
+        let eta = match watch.progress() {
+            Some(progress) if progress > 0.0 => clock(duration.mul_f64((1.0 - progress) / progress)),
+            _ => "-".to_string(),
+        };
+        let progress = format!(
+            "epoch {} - {} epochs run - {:.0} epochs/s - elapsed {} - ETA {}",
+            evolver.epoch(), watch.done(), rate, clock(duration), eta,
+        );
+        let distances = format!(
+            "best distance {:.3} - current distance {:.3} - {} shapes",
+            evolver.best_distance(), evolver.distance(), evolver.shapes().len(),
+        );
+
+        // acceptance rates of the kinds of mutation tried so far
+        let acceptance = KINDS.iter().enumerate()
+            .filter(|&(i, _)| self.tried[i] > 0)
+            .map(|(i, kind)| format!("{} {:.1}% of {}", kind, 100.0 * self.kept[i] as f64 / self.tried[i] as f64, self.tried[i]))
+            .collect::<Vec<_>>()
+            .join(" - ");
+
+        let history = &self.history;
+        let (lowest, highest) = history.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &d| (lo.min(d), hi.max(d)));
+
+        self.terminal.draw(|frame| {
+            let [gauge, text, sparkline] = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Length(3),
+                Constraint::Min(3),
+            ]).areas(frame.area());
+
+            frame.render_widget(Gauge::default().gauge_style(Style::default().fg(Color::Green)).ratio(ratio), gauge);
+
+            let lines = vec![Line::from(progress), Line::from(distances), Line::from(format!("accepted: {}", acceptance))];
+            frame.render_widget(Paragraph::new(lines), text);
+
+            // the history is resampled to the width of the sparkline, the bars
+            // going from the lowest to the highest best distance
+            let block = Block::bordered().title(format!("best distance {:.3} → {:.3}", highest, lowest));
+            let width = block.inner(sparkline).width.max(1) as usize;
+            let bars = (0..width.min(history.len()))
+                .map(|column| history[column * history.len() / width.min(history.len())])
+                .map(|distance| match highest > lowest {
+                    true => 1 + (100.0 * (distance - lowest) / (highest - lowest)) as u64,
+                    false => 1,
+                })
+                .collect::<Vec<_>>();
+            frame.render_widget(Sparkline::default().block(block).data(&bars).max(101).style(Style::default().fg(Color::Cyan)), sparkline);
+        })?;
+        Ok(())
+    }
+}
+
+// clock formats a duration as hours, minutes and seconds
+fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..=59 => format!("{:.1}s", duration.as_secs_f64()),
+        60..=3599 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m{:02}s", seconds / 3600, seconds / 60 % 60, seconds % 60),
+    }
+}
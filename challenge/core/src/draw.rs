@@ -0,0 +1,122 @@
+use crate::shape::{Point, Primitive, Shape};
+use crate::ImgRGBA;
+use image::Rgba;
+
+/// draw draws a slice of shapes into the pixel buffer
+pub fn draw(image: &mut ImgRGBA, shapes: &[Primitive]) {
+    for shape in shapes.iter() {
+        draw_shape(shape, image);
+    }
+}
+
+/// draw_shape draws a shape in a given image
+pub fn draw_shape<S: Shape + ?Sized>(shape: &S, image: &mut ImgRGBA) {
+    let color = shape.color();
+    let (w, h) = image.dimensions();
+    shape.rasterise(w, h, &mut |x, y| {
+        blend_pixel(image, x, y, &color);
+    });
+}
+
+/// blend_color blends 2 colors together, c2 being drawn over c1
+#[inline]
+pub fn blend_color(c1: &Rgba<u8>, c2: &Rgba<u8>) -> Rgba<u8> {
+    if c1[3] != 255 {
+        return blend_transparent(c1, c2);
+    }
+
+    let r1 = c1[0] as f32;
+    let g1 = c1[1] as f32;
+    let b1 = c1[2] as f32;
+
+    let r2 = c2[0] as f32;
+    let g2 = c2[1] as f32;
+    let b2 = c2[2] as f32;
+
+    let alpha = c2[3] as f32 / 255f32;
+
+    Rgba(
+        [
+            (r1 * (1. - alpha) + r2 * alpha) as u8,
+            (g1 * (1. - alpha) + g2 * alpha) as u8,
+            (b1 * (1. - alpha) + b2 * alpha) as u8,
+            255
+        ]
+    )
+}
+
+// blend_transparent blends a color over a color that isn't opaque with the
+// "over" operator on premultiplied colors, then divides the result by its alpha
+// again since images store colors that aren't premultiplied
+fn blend_transparent(c1: &Rgba<u8>, c2: &Rgba<u8>) -> Rgba<u8> {
+    let (alpha1, alpha2) = (c1[3] as f32 / 255.0, c2[3] as f32 / 255.0);
+    let alpha = alpha2 + alpha1 * (1.0 - alpha2);
+    if alpha == 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+
+    let channel = |i: usize| {
+        let premultiplied = c2[i] as f32 * alpha2 + c1[i] as f32 * alpha1 * (1.0 - alpha2);
+        (premultiplied / alpha).round() as u8
+    };
+    Rgba([channel(0), channel(1), channel(2), (alpha * 255.0).round() as u8])
+}
+
+// draw_shape_recording draws a shape and saves the coordinates of every
+// pixel it covered, so the layer can be redrawn later without rasterising it again
+pub(crate) fn draw_shape_recording(shape: &Primitive, image: &mut ImgRGBA, pixels: &mut Vec<Point>) {
+    let color = shape.color();
+    let (w, h) = image.dimensions();
+    pixels.clear();
+    shape.rasterise(w, h, &mut |x, y| {
+        blend_pixel(image, x, y, &color);
+        pixels.push(Point { x, y });
+    });
+}
+
+// draw_pixels blends the color of a shape on the pixels it covered when it was recorded
+#[inline]
+pub(crate) fn draw_pixels(shape: &Primitive, image: &mut ImgRGBA, pixels: &[Point]) {
+    let color = shape.color();
+    for pixel in pixels {
+        blend_pixel(image, pixel.x, pixel.y, &color);
+    }
+}
+
+#[inline]
+fn blend_pixel(image: &mut ImgRGBA, x: u32, y: u32, color: &Rgba<u8>) {
+    let current_pixel_color = image.get_pixel(x, y);
+    let color = blend_color(current_pixel_color, color);
+    image.put_pixel(x, y, color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_transparent_over_half_transparent() {
+        let (red, blue) = (Rgba([255, 0, 0, 128]), Rgba([0, 0, 255, 128]));
+        // alpha 0.502 + 0.502 * 0.498 = 0.752, red 255 * 0.502 / 0.752, blue 255 * 0.502 * 0.498 / 0.752
+        assert_eq!(blend_color(&blue, &red), Rgba([170, 0, 85, 192]));
+        assert_eq!(blend_color(&red, &blue), Rgba([85, 0, 170, 192]));
+    }
+
+    #[test]
+    fn transparent_colors_leave_the_other_one_alone() {
+        let color = Rgba([10, 20, 30, 128]);
+        let clear = Rgba([0, 0, 0, 0]);
+        assert_eq!(blend_color(&clear, &color), color);
+        assert_eq!(blend_color(&color, &clear), color);
+        assert_eq!(blend_color(&clear, &clear), clear);
+        // an opaque color covers whatever is under it
+        assert_eq!(blend_color(&color, &Rgba([40, 50, 60, 255])), Rgba([40, 50, 60, 255]));
+    }
+
+    #[test]
+    fn opaque_destination_stays_opaque() {
+        let blended = blend_color(&Rgba([0, 0, 255, 255]), &Rgba([255, 0, 0, 128]));
+        // the channels are truncated as in the baseline, not rounded
+        assert_eq!(blended, Rgba([128, 0, 126, 255]));
+    }
+}
@@ -0,0 +1,717 @@
+mod workers;
+
+use crate::acceptance::Acceptance;
+use crate::background::{Background, Canvas};
+use crate::backend::{self, Backend, Evaluator};
+use crate::checkpoint::{Checkpoint, CheckpointError};
+use crate::draw::draw;
+use crate::fitness::{Fitness, Metric, Objective, Weights};
+use crate::genetic::{self, Genetic, Genome, Variation};
+use crate::growth::{self, Growth};
+use crate::mutate::{mutate, restructure, ColorMutation, Mutation, MutationKind, Operator, OperatorStats, Operators, StepSize, Structure};
+use crate::shape::{random_shape, Primitive, Shape, ShapeKind};
+use crate::stop::{Stop, StopReason};
+use crate::svg::svg;
+use crate::ImgRGBA;
+use image::imageops::{self, FilterType};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use workers::Workers;
+
+/// Config holds the parameters of a run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// number of shapes drawn in the image
+    pub shapes: usize,
+    /// kinds of shape drawn, each shape is given one of them at random
+    pub kinds: Vec<ShapeKind>,
+    /// number of vertices of the polygons
+    pub vertices: usize,
+    /// whether polygons are drawn as the convex hull of their vertices,
+    /// instead of joining them in order
+    pub convex: bool,
+    /// how candidates are drawn and evaluated
+    pub backend: Backend,
+    /// how the distance between the colors of the image and the target is measured
+    pub metric: Metric,
+    /// what the distance between the image and the target measures
+    pub objective: Objective,
+    /// how much each pixel of the target counts in the distance
+    pub weights: Weights,
+    /// canvas the shapes are drawn on
+    pub background: Background,
+    /// whether the hill climber also mutates the colors of the background, as
+    /// one more gene picked as often as a shape. It needs an opaque background
+    pub evolve_background: bool,
+    /// how the color of a shape is mutated, and of the background
+    pub color_mutation: ColorMutation,
+    /// weights of the operators mutating a shape, None weighing the vertices
+    /// and the color mutation as much
+    pub operators: Option<Operators>,
+    /// whether mutations replace values at random or nudge them
+    pub mutation: Mutation,
+    /// initial step size of Gaussian mutations, as a fraction of the largest
+    /// side of the image for coordinates and of 255 for color channels
+    pub sigma: f64,
+    /// probabilities of the hill climber changing the stacking order or the number
+    /// of shapes instead of a shape, up to the number of shapes
+    pub structure: Structure,
+    /// which candidates replace the current shapes
+    pub acceptance: Acceptance,
+    /// evolves a population of shape sets with a genetic algorithm instead of
+    /// mutating a single one, each epoch being a generation
+    pub genetic: Option<Genetic>,
+    /// starts with a few shapes and adds one whenever the hill climber stops
+    /// getting closer to the target, up to the number of shapes
+    pub growth: Option<Growth>,
+    /// seed of the random number generator, runs with the same seed,
+    /// target and config produce the same shapes. A random seed is
+    /// picked when it is None
+    pub seed: Option<u64>,
+    /// number of candidates mutated and evaluated in parallel each epoch,
+    /// each on its own thread, the best one being kept. The genetic algorithm
+    /// splits the genomes of each generation between the threads instead
+    pub threads: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            shapes: 50,
+            kinds: vec![ShapeKind::Triangle],
+            vertices: 3,
+            convex: false,
+            backend: Backend::Layered,
+            metric: Metric::Rgb,
+            objective: Objective::Color,
+            weights: Weights::Uniform,
+            background: Background::White,
+            evolve_background: false,
+            color_mutation: ColorMutation::Channel,
+            operators: None,
+            mutation: Mutation::Uniform,
+            sigma: 0.05,
+            structure: Structure::default(),
+            acceptance: Acceptance::Greedy,
+            genetic: None,
+            growth: None,
+            seed: None,
+            threads: 1,
+        }
+    }
+}
+
+impl Config {
+    /// shape_operators returns the weights of the operators mutating a shape
+    pub fn shape_operators(&self) -> Operators {
+        self.operators.unwrap_or(Operators::from(self.color_mutation))
+    }
+}
+
+/// Step describes the outcome of one epoch
+#[derive(Clone, Debug)]
+pub struct Step {
+    /// epoch number, starting at 0
+    pub epoch: u64,
+    /// index of the mutated shape of the best candidate, the number of shapes when
+    /// the background was mutated, 0 with the genetic algorithm
+    pub index: usize,
+    /// what the mutation of the best candidate changed
+    pub kind: MutationKind,
+    /// operator that mutated the shape of the best candidate, None when it didn't
+    /// mutate a shape and with the genetic algorithm
+    pub operator: Option<Operator>,
+    /// distance of the best candidate to the target, the best genome of the generation
+    /// with the genetic algorithm
+    pub distance: f64,
+    /// whether the mutation was kept, which may make the shapes worse with simulated annealing.
+    /// With the genetic algorithm, whether the generation found shapes closer than ever
+    pub accepted: bool,
+}
+
+/// Evolver approximates a target image with a set of shapes
+pub struct Evolver {
+    target: ImgRGBA,
+    // the state the workers read is shared with their threads
+    fitness: Arc<dyn Fitness>,
+    config: Arc<Config>,
+    shapes: Arc<Vec<Primitive>>,
+    background: Arc<Canvas>,
+    // background painted at the target size
+    canvas: Arc<ImgRGBA>,
+    distance: f64,
+    best_shapes: Vec<Primitive>,
+    best_background: Canvas,
+    best_distance: f64,
+    epoch: u64,
+    // epoch of the last rescale, the annealing schedule starts over at each size
+    rescaled: u64,
+    seed: u64,
+    step_size: StepSize,
+    // epochs in a row without a better best distance, to know when shapes grow
+    stagnant: u64,
+    // mutations tried and kept by each operator of the hill climber
+    operator_stats: OperatorStats,
+    workers: Workers,
+    // genomes of the genetic algorithm, from the closest to the target, empty for the hill climber
+    population: Vec<Genome>,
+}
+
+// Worker mutates and evaluates candidates with its own random number
+// generator and drawing buffers, so workers can run on separate threads
+struct Worker {
+    rng: ChaCha8Rng,
+    evaluator: Box<dyn Evaluator>,
+}
+
+// Candidate is a mutation proposed by a worker
+enum Candidate {
+    // a mutated copy of the shape at the index
+    Shape(usize, Primitive, Operator),
+    // a mutated copy of the background
+    Background(Canvas),
+    // shapes in another stacking order or number, the same below the index
+    Structure(Vec<Primitive>, usize, MutationKind),
+}
+
+impl Worker {
+    // propose mutates a shape, or the background when it evolves, and returns the
+    // mutation with its distance to the target
+    fn propose(&mut self, target: &dyn Fitness, shapes: &[Primitive], background: &Canvas, config: &Config, sigma: f64) -> (Candidate, f64) {
+        let (width, height) = target.dimensions();
+
+        // the background is picked as often as each shape, it changes the whole
+        // image so the shapes are drawn again on it without touching the evaluator
+        if config.evolve_background && self.rng.gen_range(0..=shapes.len()) == shapes.len() {
+            let background = background.mutate(&mut self.rng, config.color_mutation, config.mutation, sigma);
+            let mut image = background.paint(width, height);
+            draw(&mut image, shapes);
+            let distance = target.distance(&image);
+            return (Candidate::Background(background), distance);
+        }
+
+        // the stacking order or the number of shapes changes with the probabilities of
+        // the structure, the layers from the lowest changed one being evaluated again
+        if let Some(kind) = config.structure.pick(&mut self.rng) {
+            let insert = |rng: &mut ChaCha8Rng| {
+                let shape_kind = config.kinds[rng.gen_range(0..config.kinds.len())];
+                random_shape(rng, width, height, shape_kind, config.vertices, config.convex)
+            };
+            if let Some((shapes, index)) = restructure(&mut self.rng, shapes, kind, config.shapes, insert) {
+                let distance = self.evaluator.evaluate_shapes(target, &shapes, index);
+                return (Candidate::Structure(shapes, index, kind), distance);
+            }
+        }
+
+        // mutate a shape and get the mutated copy
+        let (index, candidate, operator) = mutate(&mut self.rng, shapes, width, height, &config.shape_operators(), config.mutation, sigma);
+
+        // computes the distance between the new image and the reference image
+        let distance = self.evaluator.evaluate(target, shapes, index, &candidate);
+
+        (Candidate::Shape(index, candidate, operator), distance)
+    }
+
+    // commit keeps shapes[index] as the winning candidate, evaluating it first
+    // when the worker evaluated a candidate of its own last
+    fn commit(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize, evaluate: bool) {
+        if evaluate {
+            self.evaluator.evaluate(target, shapes, index, &shapes[index]);
+        }
+        self.evaluator.commit(target, shapes, index);
+    }
+
+    // commit_shapes keeps the shapes from the index up as the winning candidate, as commit does
+    fn commit_shapes(&mut self, target: &dyn Fitness, shapes: &[Primitive], index: usize, evaluate: bool) {
+        if evaluate {
+            self.evaluator.evaluate_shapes(target, shapes, index);
+        }
+        self.evaluator.commit_shapes(target, shapes, index);
+    }
+}
+
+impl Evolver {
+    /// new creates an evolver with random shapes for the target image
+    ///
+    /// Panics if `config.shapes` is 0, `config.kinds` is empty, `config.vertices` is less than 3,
+    /// `config.weights` can't apply to the objective or the target, the background
+    /// evolves while it is transparent, shapes grow with the genetic algorithm, the
+    /// elitism of the genetic algorithm leaves no room for children, or every operator weighs 0.
+    pub fn new(target: ImgRGBA, config: Config) -> Evolver {
+        assert!(config.shapes > 0, "an evolver needs at least one shape");
+        assert!(!config.kinds.is_empty(), "an evolver needs at least one kind of shape");
+        assert!(config.vertices >= 3, "a polygon needs at least 3 vertices");
+
+        // the target is prepared once for the background and the objective
+        let (target, fitness) = prepare(target, &config);
+        let background = config.background.canvas(&target);
+        assert!(!config.evolve_background || background.is_opaque(), "only an opaque background can evolve");
+        assert!(config.growth.is_none() || config.genetic.is_none(), "shapes only grow with the hill climber");
+        assert!(
+            config.genetic.is_none_or(|genetic| genetic.elitism < genetic.population.max(2)),
+            "the elitism must be less than the population",
+        );
+        assert!(config.shape_operators().total() > 0, "an evolver needs an operator with a weight");
+        let canvas = Arc::new(background.paint(target.width(), target.height()));
+
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        let shapes = random_shapes(&mut rng, &*fitness, &config);
+
+        // the genetic algorithm starts from these shapes and random ones
+        let genomes = config.genetic.map(|genetic| {
+            let mut genomes = vec![shapes.clone()];
+            genomes.extend((1..genetic.population.max(2)).map(|_| random_shapes(&mut rng, &*fitness, &config)));
+            genomes
+        });
+
+        let (mut workers, mut distance) = workers(&*fitness, &canvas, &shapes, &config, seed, vec![rng]);
+        let population = match genomes {
+            Some(genomes) => {
+                let mut population = workers.evaluate(&fitness, &canvas, genomes);
+                genetic::sort(&mut population);
+                population
+            }
+            None => Vec::new(),
+        };
+        let step_size = StepSize::new(config.sigma);
+
+        let shapes = match population.first() {
+            Some(best) => {
+                distance = best.distance;
+                best.shapes.clone()
+            }
+            None => shapes,
+        };
+
+        Evolver {
+            target, fitness, config: Arc::new(config), best_shapes: shapes.clone(), shapes: Arc::new(shapes),
+            best_background: background.clone(), background: Arc::new(background), canvas, distance,
+            best_distance: distance, epoch: 0, rescaled: 0, seed, step_size, stagnant: 0,
+            operator_stats: OperatorStats::default(), workers, population,
+        }
+    }
+
+    /// resume continues a run from a checkpoint, the target must be the
+    /// image the checkpoint was made for. A checkpoint saved on the target
+    /// halved a few times, during a pyramid level, goes on at the target size
+    pub fn resume(target: ImgRGBA, checkpoint: Checkpoint) -> Result<Evolver, CheckpointError> {
+        if checkpoint.shapes.is_empty() {
+            return Err(CheckpointError::NoShapes);
+        }
+        let size = (checkpoint.width, checkpoint.height);
+        let halved = |halvings: u32| ((target.width() >> halvings).max(1), (target.height() >> halvings).max(1));
+        match (0..u32::BITS).find(|&halvings| halved(halvings) == size) {
+            None => return Err(CheckpointError::TargetSize { expected: size, found: target.dimensions() }),
+            Some(0) => {}
+            Some(_) => {
+                // the level was resized from the target as the pyramid does it
+                let level = imageops::resize(&target, size.0, size.1, FilterType::Triangle);
+                let mut evolver = Evolver::resume(level, checkpoint)?;
+                evolver.rescale(target);
+                return Ok(evolver);
+            }
+        }
+        let (target, fitness) = prepare(target, &checkpoint.config);
+
+        let Checkpoint {
+            mut config, seed, rng, worker_rngs, epoch, rescaled, best_distance, shapes: best_shapes, current_shapes,
+            background: best_background, current_background, step_size, stagnant, population, ..
+        } = checkpoint;
+        // the number of shapes is the maximum they can grow to, the shapes may be fewer
+        config.shapes = config.shapes.max(best_shapes.len());
+
+        let shapes = current_shapes.unwrap_or_else(|| best_shapes.clone());
+        let best_background = best_background.unwrap_or_else(|| config.background.canvas(&target));
+        let background = current_background.unwrap_or_else(|| best_background.clone());
+        let canvas = Arc::new(background.paint(target.width(), target.height()));
+
+        let rngs = std::iter::once(rng).chain(worker_rngs).collect();
+        let (mut workers, distance) = workers(&*fitness, &canvas, &shapes, &config, seed, rngs);
+        let step_size = step_size.unwrap_or_else(|| StepSize::new(config.sigma));
+
+        // the best shapes are the current ones unless annealing moved away from them,
+        // the genetic algorithm measures them with fitness rather than with the backend
+        let unchanged = shapes == best_shapes && background == best_background;
+        let best_distance = if unchanged && config.genetic.is_none() { distance } else { best_distance };
+
+        // the genomes were saved sorted, evaluating them again gives back the same order
+        let population = match config.genetic {
+            Some(_) if !population.is_empty() => workers.evaluate(&fitness, &canvas, population),
+            Some(_) => workers.evaluate(&fitness, &canvas, vec![best_shapes.clone()]),
+            None => Vec::new(),
+        };
+
+        let mut evolver = Evolver {
+            target, fitness, config: Arc::new(config), shapes: Arc::new(shapes), background: Arc::new(background), canvas,
+            distance, best_shapes, best_background, best_distance,
+            epoch, rescaled, seed, step_size, stagnant, operator_stats: OperatorStats::default(), workers, population,
+        };
+        evolver.adopt_best_genome();
+        Ok(evolver)
+    }
+
+    /// checkpoint returns the state of the run, to be resumed later
+    pub fn checkpoint(&self) -> Checkpoint {
+        let mut rngs = self.workers.rngs().into_iter();
+        let rng = rngs.next().unwrap();
+        let worker_rngs = rngs.collect();
+        Checkpoint {
+            config: (*self.config).clone(),
+            seed: self.seed,
+            rng,
+            worker_rngs,
+            epoch: self.epoch,
+            rescaled: self.rescaled,
+            width: self.target.dimensions().0,
+            height: self.target.dimensions().1,
+            best_distance: self.best_distance,
+            shapes: self.best_shapes.clone(),
+            current_shapes: (*self.shapes != self.best_shapes).then(|| self.shapes.to_vec()),
+            background: Some(self.best_background.clone()),
+            current_background: (*self.background != self.best_background).then(|| (*self.background).clone()),
+            step_size: Some(self.step_size),
+            stagnant: self.stagnant,
+            population: self.population.iter().map(|genome| genome.shapes.clone()).collect(),
+        }
+    }
+
+    /// step mutates a shape and keeps the mutation if the acceptance policy allows it,
+    /// with several threads each one mutates a shape and the best mutation is considered
+    pub fn step(&mut self) -> Step {
+        if let Some(genetic) = self.config.genetic {
+            return self.generation(genetic);
+        }
+
+        let sigma = self.step_size.sigma();
+        let proposals = self.workers.propose(&self.fitness, &self.config, &self.shapes, &self.background, sigma);
+
+        // the first of the closest candidates wins, so runs don't depend on thread scheduling
+        let mut winner = 0;
+        for (i, proposal) in proposals.iter().enumerate() {
+            if proposal.1 < proposals[winner].1 {
+                winner = i;
+            }
+        }
+        let (candidate, distance) = proposals.into_iter().nth(winner).unwrap();
+        let (index, kind, operator) = match candidate {
+            Candidate::Shape(index, _, operator) => (index, operator.kind(), Some(operator)),
+            Candidate::Background(_) => (self.shapes.len(), MutationKind::Background, None),
+            Candidate::Structure(_, index, kind) => (index, kind, None),
+        };
+
+        // greedy acceptance keeps the mutation if the new distance is better than the current one,
+        // annealing draws from the first worker's generator so runs stay reproducible
+        let improved = distance < self.distance;
+        let best_distance = self.best_distance;
+        let accepted = self.config.acceptance.accept(self.workers.rng(), self.distance, distance, self.epoch - self.rescaled);
+
+        // only the winner is counted, the other proposals never had a chance to be kept
+        if let Some(operator) = operator {
+            self.operator_stats.count_tried(operator);
+        }
+        if accepted {
+            if let Some(operator) = operator {
+                self.operator_stats.count_kept(operator);
+            }
+            match candidate {
+                Candidate::Shape(index, shape, _) => {
+                    Arc::make_mut(&mut self.shapes)[index] = shape;
+                    self.commit(winner, index);
+                }
+                Candidate::Background(background) => {
+                    self.background = Arc::new(background);
+                    self.repaint();
+                }
+                Candidate::Structure(shapes, index, _) => {
+                    self.shapes = Arc::new(shapes);
+                    self.commit_shapes(winner, index);
+                }
+            }
+            self.distance = distance;
+            if distance < self.best_distance {
+                self.best_shapes.clone_from(&self.shapes);
+                self.best_background.clone_from(&self.background);
+                self.best_distance = distance;
+            }
+        }
+        if self.config.mutation == Mutation::Gaussian {
+            // the 1/5th rule counts improvements, not the worse mutations annealing lets through
+            self.step_size.update(improved);
+        }
+
+        // a shape is added once the best distance stops improving for long enough
+        if let Some(growth) = self.config.growth {
+            self.stagnant = if self.best_distance < best_distance { 0 } else { self.stagnant + 1 };
+            if self.stagnant >= growth.patience && self.shapes.len() < self.config.shapes {
+                self.grow();
+                self.stagnant = 0;
+            }
+        }
+
+        let step = Step { epoch: self.epoch, index, kind, operator, distance, accepted };
+        self.epoch += 1;
+        step
+    }
+
+    // generation breeds the next generation of the genetic algorithm, whose
+    // best genome becomes the current shapes
+    fn generation(&mut self, genetic: Genetic) -> Step {
+        let variation = Variation {
+            operators: self.config.shape_operators(),
+            mutation: self.config.mutation,
+            sigma: self.step_size.sigma(),
+        };
+        let children = genetic::breed(self.workers.rng(), self.fitness.dimensions(), &self.population, &genetic, &variation);
+        let children = self.workers.evaluate(&self.fitness, &self.canvas, children);
+        self.population = genetic::next_generation(&self.population, &genetic, children);
+
+        let accepted = self.population[0].distance < self.best_distance;
+        self.adopt_best_genome();
+        if self.config.mutation == Mutation::Gaussian {
+            self.step_size.update(accepted);
+        }
+
+        let step = Step { epoch: self.epoch, index: 0, kind: MutationKind::Generation, operator: None, distance: self.distance, accepted };
+        self.epoch += 1;
+        step
+    }
+
+    // adopt_best_genome makes the best genome of the population the current shapes,
+    // and the best shapes when it is closer than them
+    fn adopt_best_genome(&mut self) {
+        let Some(best) = self.population.first() else { return };
+
+        Arc::make_mut(&mut self.shapes).clone_from(&best.shapes);
+        self.distance = best.distance;
+        if best.distance < self.best_distance {
+            self.best_shapes.clone_from(&best.shapes);
+            self.best_distance = best.distance;
+        }
+    }
+
+    // commit updates the state of every worker after shapes[index] was replaced
+    // by the candidate of the winner, the other workers evaluated their own
+    // candidate last so they evaluate the winning one before keeping it
+    fn commit(&mut self, winner: usize, index: usize) {
+        self.workers.commit(&self.fitness, &self.shapes, index, winner);
+    }
+
+    // commit_shapes updates the state of every worker after the shapes from the index
+    // up were replaced by the ones of the winner, as commit does
+    fn commit_shapes(&mut self, winner: usize, index: usize) {
+        self.workers.commit_shapes(&self.fitness, &self.shapes, index, winner);
+    }
+
+    // repaint paints the canvas again after the background, the size or the number
+    // of shapes changed, draws the shapes of every worker on it and returns their distance
+    fn repaint(&mut self) -> f64 {
+        let (width, height) = self.target.dimensions();
+        self.canvas = Arc::new(self.background.paint(width, height));
+        self.workers.reset(&self.fitness, &self.canvas, &self.shapes)
+    }
+
+    // grow adds a shape on top of the others where the image is farthest from the
+    // target, and keeps it even if it makes the image worse
+    fn grow(&mut self) {
+        // the layered backend already holds the local distances, the image is drawn otherwise
+        let (width, height) = self.target.dimensions();
+        let area = match self.workers.local_distances() {
+            Some(distances) => growth::largest_error(width, height, |x, y| distances[(y * width + x) as usize]),
+            None => {
+                let mut image = (*self.canvas).clone();
+                draw(&mut image, &self.shapes);
+                growth::largest_error(width, height, |x, y| self.fitness.local_distance(&image, x, y))
+            }
+        };
+
+        let config = &self.config;
+        let shape = growth::shape_in(self.workers.rng(), area, &self.target, &config.kinds, config.vertices, config.convex);
+        Arc::make_mut(&mut self.shapes).push(shape);
+
+        self.distance = self.repaint();
+        if self.distance < self.best_distance {
+            self.best_shapes.clone_from(&self.shapes);
+            self.best_background.clone_from(&self.background);
+            self.best_distance = self.distance;
+        }
+    }
+
+    /// rescale continues the run on the target at another size, such as a smaller
+    /// copy first then the target itself, the shapes being stretched to the new
+    /// size. A weights mask is resized with the target, and simulated annealing
+    /// starts its schedule over from the first temperature
+    pub fn rescale(&mut self, target: ImgRGBA) {
+        let (width, height) = self.target.dimensions();
+        let (new_width, new_height) = target.dimensions();
+        self.rescaled = self.epoch;
+        let (sx, sy) = (new_width as f64 / width as f64, new_height as f64 / height as f64);
+
+        let config = Arc::make_mut(&mut self.config);
+        config.weights = config.weights.resize((width, height), (new_width, new_height));
+        (self.target, self.fitness) = prepare(target, &self.config);
+
+        let population = self.population.drain(..).map(|genome| genome.shapes);
+        let mut genomes: Vec<Vec<Primitive>> = population.collect();
+        for shape in Arc::make_mut(&mut self.shapes).iter_mut().chain(&mut self.best_shapes).chain(genomes.iter_mut().flatten()) {
+            shape.rescale(sx, sy, new_width, new_height);
+        }
+
+        self.distance = self.repaint();
+
+        // distances at another size don't compare, the best shapes are measured again
+        self.best_distance = if *self.shapes == self.best_shapes && *self.background == self.best_background {
+            self.distance
+        } else {
+            let mut image = self.best_background.paint(new_width, new_height);
+            draw(&mut image, &self.best_shapes);
+            self.fitness.distance(&image)
+        };
+
+        if self.config.genetic.is_some() {
+            self.population = self.workers.evaluate(&self.fitness, &self.canvas, genomes);
+            genetic::sort(&mut self.population);
+            self.adopt_best_genome();
+        }
+    }
+
+    /// run runs the given number of epochs
+    pub fn run(&mut self, epochs: u64) {
+        for _ in 0..epochs {
+            self.step();
+        }
+    }
+
+    /// run_until runs epochs until one of the conditions is met and returns it,
+    /// forever without any condition
+    pub fn run_until(&mut self, stop: Stop) -> StopReason {
+        let mut watch = stop.watch(self);
+        loop {
+            self.step();
+            if let Some(reason) = watch.check(self) {
+                return reason;
+            }
+        }
+    }
+
+    /// shapes returns the current shapes, in stacking order, which annealing
+    /// may have moved away from the best ones
+    pub fn shapes(&self) -> &[Primitive] {
+        &self.shapes
+    }
+
+    /// distance returns the distance between the current shapes and the target
+    pub fn distance(&self) -> f64 {
+        self.distance
+    }
+
+    /// temperature returns the temperature of simulated annealing for the next epoch, 0 when greedy
+    pub fn temperature(&self) -> f64 {
+        self.config.acceptance.temperature(self.epoch - self.rescaled)
+    }
+
+    /// best_shapes returns the shapes of the closest image found so far, in stacking order
+    pub fn best_shapes(&self) -> &[Primitive] {
+        &self.best_shapes
+    }
+
+    /// best_distance returns the distance between the closest image found so far and the target
+    pub fn best_distance(&self) -> f64 {
+        self.best_distance
+    }
+
+    /// epoch returns the number of epochs run so far
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// operator_stats returns the mutations tried and kept by each operator since the
+    /// evolver was created or resumed, with the hill climber
+    pub fn operator_stats(&self) -> &OperatorStats {
+        &self.operator_stats
+    }
+
+    /// sigma returns the current step size of Gaussian mutations
+    pub fn sigma(&self) -> f64 {
+        self.step_size.sigma()
+    }
+
+    /// seed returns the seed of the random number generator, which is
+    /// the one of the config or the one picked when the config has none
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// config returns the parameters of the run
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// target returns the image being approximated, blended over the background when it is opaque
+    pub fn target(&self) -> &ImgRGBA {
+        &self.target
+    }
+
+    /// render draws the best shapes on their background, at the target size
+    pub fn render(&self) -> ImgRGBA {
+        let (width, height) = self.target.dimensions();
+        let mut image = self.best_background.paint(width, height);
+        draw(&mut image, &self.best_shapes);
+        image
+    }
+
+    /// render_svg returns the best shapes as an SVG document of the target size
+    pub fn render_svg(&self) -> String {
+        let (width, height) = self.target.dimensions();
+        svg(&self.best_shapes, width, height, &self.best_background.tiles(width, height))
+    }
+}
+
+// prepare returns the target as the background needs it and its fitness
+fn prepare(target: ImgRGBA, config: &Config) -> (ImgRGBA, Arc<dyn Fitness>) {
+    let target = config.background.target(target);
+    let fitness = config.weights.weigh(config.objective.fitness(&target, config.metric), &target);
+    (target, Arc::from(fitness))
+}
+
+// random_shapes creates the random shapes a run starts from, fewer than the
+// number of shapes when they grow
+fn random_shapes<R: Rng>(rng: &mut R, target: &dyn Fitness, config: &Config) -> Vec<Primitive> {
+    let (width, height) = target.dimensions();
+    let count = config.growth.map_or(config.shapes, |growth| growth.start.clamp(1, config.shapes));
+    (0..count)
+        .map(|_| {
+            let kind = config.kinds[rng.gen_range(0..config.kinds.len())];
+            random_shape(rng, width, height, kind, config.vertices, config.convex)
+        })
+        .collect()
+}
+
+// workers creates one worker per thread of the config, reusing the given random
+// number generators and deriving the missing ones from the seed, one stream per
+// worker. It returns them with the distance of the shapes
+fn workers(target: &dyn Fitness, canvas: &ImgRGBA, shapes: &[Primitive], config: &Config, seed: u64, mut rngs: Vec<ChaCha8Rng>) -> (Workers, f64) {
+    let threads = config.threads.max(1);
+
+    rngs.truncate(threads);
+    while rngs.len() < threads {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(rngs.len() as u64);
+        rngs.push(rng);
+    }
+
+    let mut distance = 0.0;
+    let workers = rngs.into_iter()
+        .map(|rng| {
+            let mut evaluator = backend::evaluator(config.backend);
+            distance = evaluator.reset(target, canvas, shapes);
+            Worker { rng, evaluator }
+        })
+        .collect();
+
+    (Workers::new(workers), distance)
+}
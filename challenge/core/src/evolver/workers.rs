@@ -0,0 +1,229 @@
+use super::{Candidate, Config, Worker};
+use crate::background::Canvas;
+use crate::fitness::Fitness;
+use crate::genetic::{self, Genome};
+use crate::shape::Primitive;
+use crate::ImgRGBA;
+use rand_chacha::ChaCha8Rng;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+// Workers holds the workers of an evolver. The first one runs on the calling
+// thread and the others each on a thread that lives as long as the evolver, so
+// an epoch only sends them jobs instead of starting threads
+pub(super) struct Workers {
+    first: Worker,
+    others: Vec<Remote>,
+}
+
+// Remote is a worker on its own thread, which runs the jobs it receives in order
+struct Remote {
+    jobs: Option<Sender<Job>>,
+    outputs: Receiver<Output>,
+    handle: Option<JoinHandle<()>>,
+}
+
+// Job is what a remote worker is asked to do, with the state of the evolver it needs
+enum Job {
+    Propose(Arc<dyn Fitness>, Arc<Config>, Arc<Vec<Primitive>>, Arc<Canvas>, f64),
+    // the flag tells whether the worker evaluates the winning candidate before keeping it
+    Commit(Arc<dyn Fitness>, Arc<Vec<Primitive>>, usize, bool),
+    CommitShapes(Arc<dyn Fitness>, Arc<Vec<Primitive>>, usize, bool),
+    Reset(Arc<dyn Fitness>, Arc<ImgRGBA>, Arc<Vec<Primitive>>),
+    Evaluate(Arc<dyn Fitness>, Arc<ImgRGBA>, Vec<Vec<Primitive>>),
+    Rng,
+}
+
+// Output is what a remote worker returns for a job
+enum Output {
+    Proposal(Candidate, f64),
+    Distance(f64),
+    Genomes(Vec<Genome>),
+    Rng(Box<ChaCha8Rng>),
+    Done,
+}
+
+impl Workers {
+    // new starts a thread for every worker but the first
+    pub(super) fn new(mut workers: Vec<Worker>) -> Workers {
+        let others = workers.split_off(1).into_iter().map(Remote::spawn).collect();
+        Workers { first: workers.remove(0), others }
+    }
+
+    // len returns the number of workers
+    pub(super) fn len(&self) -> usize {
+        1 + self.others.len()
+    }
+
+    // rng returns the random number generator of the first worker, which draws
+    // everything that doesn't belong to a worker so runs stay reproducible
+    pub(super) fn rng(&mut self) -> &mut ChaCha8Rng {
+        &mut self.first.rng
+    }
+
+    // local_distances returns the local distance of each pixel of the current
+    // image, when the backend of the first worker keeps them
+    pub(super) fn local_distances(&self) -> Option<&[f64]> {
+        self.first.evaluator.local_distances()
+    }
+
+    // rngs returns the state of the random number generator of every worker
+    pub(super) fn rngs(&self) -> Vec<ChaCha8Rng> {
+        self.send(|_| Job::Rng);
+        let mut rngs = vec![self.first.rng.clone()];
+        rngs.extend(self.receive().map(|output| match output {
+            Output::Rng(rng) => *rng,
+            _ => unreachable!("a worker answered another job"),
+        }));
+        rngs
+    }
+
+    // propose has every worker propose a candidate, in the order of the workers
+    pub(super) fn propose(
+        &mut self,
+        target: &Arc<dyn Fitness>,
+        config: &Arc<Config>,
+        shapes: &Arc<Vec<Primitive>>,
+        background: &Arc<Canvas>,
+        sigma: f64,
+    ) -> Vec<(Candidate, f64)> {
+        let job = |_| Job::Propose(target.clone(), config.clone(), shapes.clone(), background.clone(), sigma);
+        let local = |worker: &mut Worker| {
+            let (candidate, distance) = worker.propose(&**target, shapes, background, config, sigma);
+            Output::Proposal(candidate, distance)
+        };
+        self.run(job, local)
+            .into_iter()
+            .map(|output| match output {
+                Output::Proposal(candidate, distance) => (candidate, distance),
+                _ => unreachable!("a worker answered another job"),
+            })
+            .collect()
+    }
+
+    // commit has every worker keep the shape at the index, proposed by the winner
+    pub(super) fn commit(&mut self, target: &Arc<dyn Fitness>, shapes: &Arc<Vec<Primitive>>, index: usize, winner: usize) {
+        let job = |i| Job::Commit(target.clone(), shapes.clone(), index, i != winner);
+        let local = |worker: &mut Worker| {
+            worker.commit(&**target, shapes, index, winner != 0);
+            Output::Done
+        };
+        self.run(job, local);
+    }
+
+    // commit_shapes has every worker keep the shapes from the index up, proposed by the winner
+    pub(super) fn commit_shapes(&mut self, target: &Arc<dyn Fitness>, shapes: &Arc<Vec<Primitive>>, index: usize, winner: usize) {
+        let job = |i| Job::CommitShapes(target.clone(), shapes.clone(), index, i != winner);
+        let local = |worker: &mut Worker| {
+            worker.commit_shapes(&**target, shapes, index, winner != 0);
+            Output::Done
+        };
+        self.run(job, local);
+    }
+
+    // reset has every worker draw the shapes on the canvas again, and returns
+    // the distance measured by the first one
+    pub(super) fn reset(&mut self, target: &Arc<dyn Fitness>, canvas: &Arc<ImgRGBA>, shapes: &Arc<Vec<Primitive>>) -> f64 {
+        let job = |_| Job::Reset(target.clone(), canvas.clone(), shapes.clone());
+        let local = |worker: &mut Worker| Output::Distance(worker.evaluator.reset(&**target, canvas, shapes));
+        match self.run(job, local)[0] {
+            Output::Distance(distance) => distance,
+            _ => unreachable!("a worker answered another job"),
+        }
+    }
+
+    // evaluate measures the genomes of the genetic algorithm, split in one run of
+    // consecutive genomes per worker, and returns them in the order they came in
+    pub(super) fn evaluate(&mut self, target: &Arc<dyn Fitness>, canvas: &Arc<ImgRGBA>, mut shapes: Vec<Vec<Primitive>>) -> Vec<Genome> {
+        let chunk = shapes.len().div_ceil(self.len());
+        // the runs are split off from the end, the last one first
+        let mut chunks: Vec<_> = (1..self.len()).rev().map(|i| shapes.split_off((chunk * i).min(shapes.len()))).collect();
+        chunks.reverse();
+        for (remote, chunk) in self.others.iter().zip(chunks) {
+            remote.jobs.as_ref().unwrap().send(Job::Evaluate(target.clone(), canvas.clone(), chunk)).expect("a worker thread stopped");
+        }
+
+        let mut genomes = genetic::evaluate(&**target, canvas, shapes);
+        genomes.extend(self.receive().flat_map(|output| match output {
+            Output::Genomes(genomes) => genomes,
+            _ => unreachable!("a worker answered another job"),
+        }));
+        genomes
+    }
+
+    // run sends its job to every remote worker, runs the local job on the first
+    // worker meanwhile, then returns the outputs in the order of the workers
+    fn run(&mut self, job: impl Fn(usize) -> Job, local: impl FnOnce(&mut Worker) -> Output) -> Vec<Output> {
+        self.send(job);
+        let mut outputs = Vec::with_capacity(self.len());
+        outputs.push(local(&mut self.first));
+        outputs.extend(self.receive());
+        outputs
+    }
+
+    // send sends every remote worker the job made for its index
+    fn send(&self, job: impl Fn(usize) -> Job) {
+        for (i, remote) in self.others.iter().enumerate() {
+            remote.jobs.as_ref().unwrap().send(job(i + 1)).expect("a worker thread stopped");
+        }
+    }
+
+    // receive waits for the output of every remote worker, in their order
+    fn receive(&self) -> impl Iterator<Item = Output> + '_ {
+        self.others.iter().map(|remote| remote.outputs.recv().expect("a worker thread panicked"))
+    }
+}
+
+impl Drop for Workers {
+    // drop stops the threads of the remote workers, which end once their jobs are closed
+    fn drop(&mut self) {
+        for remote in &mut self.others {
+            remote.jobs.take();
+        }
+        for remote in &mut self.others {
+            if let Some(handle) = remote.handle.take() {
+                // a panic of the worker was already reported by the job that saw it
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl Remote {
+    // spawn starts the thread of a worker
+    fn spawn(worker: Worker) -> Remote {
+        let (jobs, job_receiver) = mpsc::channel();
+        let (output_sender, outputs) = mpsc::channel();
+        let handle = thread::spawn(move || serve(worker, job_receiver, output_sender));
+        Remote { jobs: Some(jobs), outputs, handle: Some(handle) }
+    }
+}
+
+// serve runs the jobs of a remote worker until they are closed. A job is dropped
+// before its output is sent, so the evolver holds the only copy of its state again
+// by the time it gets the outputs and can change it without copying it
+fn serve(mut worker: Worker, jobs: Receiver<Job>, outputs: Sender<Output>) {
+    for job in jobs {
+        let output = match job {
+            Job::Propose(target, config, shapes, background, sigma) => {
+                let (candidate, distance) = worker.propose(&*target, &shapes, &background, &config, sigma);
+                Output::Proposal(candidate, distance)
+            }
+            Job::Commit(target, shapes, index, evaluate) => {
+                worker.commit(&*target, &shapes, index, evaluate);
+                Output::Done
+            }
+            Job::CommitShapes(target, shapes, index, evaluate) => {
+                worker.commit_shapes(&*target, &shapes, index, evaluate);
+                Output::Done
+            }
+            Job::Reset(target, canvas, shapes) => Output::Distance(worker.evaluator.reset(&*target, &canvas, &shapes)),
+            Job::Evaluate(target, canvas, shapes) => Output::Genomes(genetic::evaluate(&*target, &canvas, shapes)),
+            Job::Rng => Output::Rng(Box::new(worker.rng.clone())),
+        };
+        if outputs.send(output).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,338 @@
+mod edges;
+mod ssim;
+mod weights;
+
+pub use edges::EdgeWeighted;
+pub use ssim::{MsSsim, Ssim};
+pub use weights::Weights;
+
+use crate::ImgRGBA;
+use image::Rgba;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Fitness measures how far an image is from the target. The distance of most
+/// objectives is the mean of local distances around each pixel, which lets the
+/// layered backend only recompute the local distances near the mutated shape
+pub trait Fitness: Send + Sync {
+    /// dimensions returns the width and the height of the target image
+    fn dimensions(&self) -> (u32, u32);
+
+    /// reach returns how far from a pixel its local distance looks, 0 when it only
+    /// depends on the pixel itself, or None when the distance of the image isn't
+    /// the mean of the local distances and has to be computed as a whole
+    fn reach(&self) -> Option<u32>;
+
+    /// local_distance returns the distance around the pixel (x, y) of an image of the target size
+    fn local_distance(&self, image: &ImgRGBA, x: u32, y: u32) -> f64;
+
+    /// distance returns the distance between an image of the target size and the target
+    fn distance(&self, image: &ImgRGBA) -> f64 {
+        let (width, height) = self.dimensions();
+        let mut total = 0.0;
+        for y in 0..height {
+            for x in 0..width {
+                total += self.local_distance(image, x, y);
+            }
+        }
+        total / (width as f64 * height as f64)
+    }
+}
+
+/// Objective selects the Fitness a run minimises
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// mean distance between the colors of each pixel, measured with the metric
+    #[default]
+    Color,
+    /// structural similarity, which compares the mean, contrast and correlation of 7x7 windows
+    Ssim,
+    /// structural similarity over a pyramid of halved images, which also weighs large structures
+    MsSsim,
+    /// color distance weighted by the edges of the target, found with a Sobel filter
+    Edges,
+}
+
+impl Objective {
+    /// fitness creates the Fitness of the objective for a target image, with
+    /// the metric measuring color distances where the objective uses them
+    pub fn fitness(self, target: &ImgRGBA, metric: Metric) -> Box<dyn Fitness> {
+        match self {
+            Objective::Color => Box::new(Reference::new(target.clone(), metric)),
+            Objective::Ssim => Box::new(Ssim::new(target)),
+            Objective::MsSsim => Box::new(MsSsim::new(target)),
+            Objective::Edges => Box::new(EdgeWeighted::new(target, metric)),
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Objective::Color => "color",
+            Objective::Ssim => "ssim",
+            Objective::MsSsim => "ms-ssim",
+            Objective::Edges => "edges",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Metric selects how the distance between 2 colors is measured, all of them
+/// giving about 0 to 100 so distances stay comparable
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Euclidean distance in RGB, divided by 2.55
+    #[default]
+    Rgb,
+    /// CIE 1976 ΔE, the Euclidean distance in CIELAB
+    DeltaE76,
+    /// CIEDE2000 ΔE, which corrects CIELAB for the hue and saturation the eye is less sensitive to
+    DeltaE2000,
+    /// Euclidean distance in YCbCr, the brightness weighing 4 times as much as each chroma channel
+    #[serde(rename = "ycbcr")]
+    #[cfg_attr(feature = "cli", value(name = "ycbcr"))]
+    YCbCr,
+}
+
+impl Metric {
+    /// convert returns the coordinates of an RGB color in the color space of the metric
+    #[inline]
+    pub fn convert(self, color: &Rgba<u8>) -> [f32; 3] {
+        match self {
+            Metric::Rgb => [color[0] as f32, color[1] as f32, color[2] as f32],
+            Metric::DeltaE76 | Metric::DeltaE2000 => lab(color),
+            Metric::YCbCr => ycbcr(color),
+        }
+    }
+
+    /// distance returns the distance between 2 colors converted with convert
+    #[inline]
+    pub fn distance(self, c1: &[f32; 3], c2: &[f32; 3]) -> f64 {
+        match self {
+            Metric::Rgb => euclidean(c1, c2, [1.0, 1.0, 1.0]) / 2.55,
+            Metric::DeltaE76 => euclidean(c1, c2, [1.0, 1.0, 1.0]),
+            Metric::DeltaE2000 => delta_e2000(c1, c2),
+            // the weights add up to 1, so the distance stays within the one of RGB
+            Metric::YCbCr => euclidean(c1, c2, [4.0 / 6.0, 1.0 / 6.0, 1.0 / 6.0]) / 2.55,
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Metric::Rgb => "rgb",
+            Metric::DeltaE76 => "delta-e76",
+            Metric::DeltaE2000 => "delta-e2000",
+            Metric::YCbCr => "ycbcr",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Reference is the target image together with its colors converted once to
+/// the color space of a metric, so only the drawn image is converted per pixel
+pub struct Reference {
+    image: ImgRGBA,
+    metric: Metric,
+    colors: Vec<[f32; 3]>,
+}
+
+impl Reference {
+    /// new converts the colors of the target image for the metric
+    pub fn new(image: ImgRGBA, metric: Metric) -> Reference {
+        let colors = image.pixels().map(|pixel| metric.convert(pixel)).collect();
+        Reference { image, metric, colors }
+    }
+
+    /// image returns the target image
+    pub fn image(&self) -> &ImgRGBA {
+        &self.image
+    }
+
+    /// metric returns the metric the colors were converted for
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// pixel_distance returns the distance between a color and the target pixel at (x, y)
+    #[inline]
+    pub fn pixel_distance(&self, x: u32, y: u32, color: &Rgba<u8>) -> f64 {
+        let i = (y * self.image.width() + x) as usize;
+        self.color_distance(color, &self.colors[i], self.image.as_raw()[4 * i + 3])
+    }
+
+    // color_distance returns the distance between a color and a converted target
+    // color of the given alpha. When either one isn't opaque, the difference of
+    // the colors counts as much as the least opaque one, and the difference of
+    // alpha is added to it as a 4th coordinate, from 0 to 100
+    #[inline]
+    fn color_distance(&self, color: &Rgba<u8>, target: &[f32; 3], target_alpha: u8) -> f64 {
+        let distance = self.metric.distance(&self.metric.convert(color), target);
+        if color[3] == 255 && target_alpha == 255 {
+            return distance;
+        }
+
+        let (alpha, target_alpha) = (color[3] as f64 / 255.0, target_alpha as f64 / 255.0);
+        let colors = alpha.min(target_alpha) * distance;
+        let alphas = 100.0 * (alpha - target_alpha);
+        (colors * colors + alphas * alphas).sqrt()
+    }
+}
+
+impl Fitness for Reference {
+    fn dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+
+    fn reach(&self) -> Option<u32> {
+        Some(0)
+    }
+
+    #[inline]
+    fn local_distance(&self, image: &ImgRGBA, x: u32, y: u32) -> f64 {
+        self.pixel_distance(x, y, image.get_pixel(x, y))
+    }
+
+    fn distance(&self, image: &ImgRGBA) -> f64 {
+        let total: f64 = image.pixels()
+            .zip(self.colors.iter().zip(self.image.pixels()))
+            .map(|(pixel, (target, target_pixel))| self.color_distance(pixel, target, target_pixel[3]))
+            .sum();
+        total / self.colors.len() as f64
+    }
+}
+
+// euclidean returns the weighted Euclidean distance, computed in f64 so the
+// RGB metric gives exactly the distance of the baseline
+#[inline]
+fn euclidean(c1: &[f32; 3], c2: &[f32; 3], weights: [f64; 3]) -> f64 {
+    let d = |i: usize| c1[i] as f64 - c2[i] as f64;
+    (weights[0] * d(0) * d(0) + weights[1] * d(1) * d(1) + weights[2] * d(2) * d(2)).sqrt()
+}
+
+// lab converts an sRGB color to CIELAB under the D65 illuminant
+#[inline]
+fn lab(color: &Rgba<u8>) -> [f32; 3] {
+    let linear = linear_table();
+    let (r, g, b) = (linear[color[0] as usize], linear[color[1] as usize], linear[color[2] as usize]);
+
+    // XYZ scaled by the white point, so white is (1, 1, 1)
+    let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / 1.08883;
+
+    let f = |t: f32| if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+// linear_table maps each sRGB channel value to its linear intensity, from 0 to 1
+fn linear_table() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let c = i as f32 / 255.0;
+            if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        })
+    })
+}
+
+// ycbcr converts an RGB color to YCbCr as in JPEG, from 0 to 255
+#[inline]
+fn ycbcr(color: &Rgba<u8>) -> [f32; 3] {
+    let (r, g, b) = (color[0] as f32, color[1] as f32, color[2] as f32);
+    [
+        0.299 * r + 0.587 * g + 0.114 * b,
+        128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b,
+        128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b,
+    ]
+}
+
+// delta_e2000 returns the CIEDE2000 color difference of 2 CIELAB colors
+fn delta_e2000(lab1: &[f32; 3], lab2: &[f32; 3]) -> f64 {
+    let (l1, a1, b1) = (lab1[0] as f64, lab1[1] as f64, lab1[2] as f64);
+    let (l2, a2, b2) = (lab2[0] as f64, lab2[1] as f64, lab2[2] as f64);
+
+    let c_mean = ((a1 * a1 + b1 * b1).sqrt() + (a2 * a2 + b2 * b2).sqrt()) / 2.0;
+    let c_mean7 = c_mean.powi(7);
+    let g = 0.5 * (1.0 - (c_mean7 / (c_mean7 + 25f64.powi(7))).sqrt());
+
+    let (a1, a2) = (a1 * (1.0 + g), a2 * (1.0 + g));
+    let (c1, c2) = ((a1 * a1 + b1 * b1).sqrt(), (a2 * a2 + b2 * b2).sqrt());
+    let hue = |b: f64, a: f64| if a == 0.0 && b == 0.0 { 0.0 } else { b.atan2(a).to_degrees().rem_euclid(360.0) };
+    let (h1, h2) = (hue(b1, a1), hue(b2, a2));
+
+    let delta_l = l2 - l1;
+    let delta_c = c2 - c1;
+    let delta_h = if c1 * c2 == 0.0 {
+        0.0
+    } else if (h2 - h1).abs() <= 180.0 {
+        h2 - h1
+    } else if h2 <= h1 {
+        h2 - h1 + 360.0
+    } else {
+        h2 - h1 - 360.0
+    };
+    let delta_h = 2.0 * (c1 * c2).sqrt() * (delta_h / 2.0).to_radians().sin();
+
+    let l_mean = (l1 + l2) / 2.0;
+    let c_mean = (c1 + c2) / 2.0;
+    let h_mean = if c1 * c2 == 0.0 {
+        h1 + h2
+    } else if (h1 - h2).abs() <= 180.0 {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) / 2.0
+    } else {
+        (h1 + h2 - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (h_mean - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_mean).to_radians().cos()
+        + 0.32 * (3.0 * h_mean + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_mean - 63.0).to_radians().cos();
+    let l50 = (l_mean - 50.0) * (l_mean - 50.0);
+    let s_l = 1.0 + 0.015 * l50 / (20.0 + l50).sqrt();
+    let s_c = 1.0 + 0.045 * c_mean;
+    let s_h = 1.0 + 0.015 * c_mean * t;
+
+    let c_mean7 = c_mean.powi(7);
+    let r_c = 2.0 * (c_mean7 / (c_mean7 + 25f64.powi(7))).sqrt();
+    let delta_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+    let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+    let (l, c, h) = (delta_l / s_l, delta_c / s_c, delta_h / s_h);
+    (l * l + c * c + h * h + r_t * c * h).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metric;
+
+    // pairs of CIELAB colors with their CIEDE2000 difference, from the test data of
+    // Sharma, Wu and Dalal, "The CIEDE2000 color-difference formula" (2005)
+    const SHARMA: [([f32; 3], [f32; 3], f64); 5] = [
+        ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+        ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+        ([50.0, 2.49, -0.001], [50.0, -2.49, 0.0009], 7.1792),
+        ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+        ([60.2574, -34.0099, 36.2677], [60.4626, -34.1751, 39.4387], 1.2644),
+    ];
+
+    #[test]
+    fn delta_e2000_matches_reference_data() {
+        for (lab1, lab2, expected) in SHARMA {
+            for (c1, c2) in [(lab1, lab2), (lab2, lab1)] {
+                let distance = Metric::DeltaE2000.distance(&c1, &c2);
+                assert!((distance - expected).abs() < 5e-5, "{:?} {:?}: {} instead of {}", c1, c2, distance, expected);
+            }
+        }
+    }
+}
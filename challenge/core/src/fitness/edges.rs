@@ -0,0 +1,73 @@
+use super::{Fitness, Metric, Reference};
+use crate::ImgRGBA;
+
+// how much more a pixel on the strongest edge of the target weighs than a pixel on a flat area
+const EDGE_GAIN: f32 = 4.0;
+
+/// EdgeWeighted is the color distance of each pixel weighted by the strength of
+/// the edges of the target at the pixel, found with a Sobel filter on its
+/// brightness, so outlines count more than flat areas. The weights have a mean
+/// of 1, so the distance stays comparable with the plain color distance
+pub struct EdgeWeighted {
+    reference: Reference,
+    weights: Vec<f32>,
+}
+
+impl EdgeWeighted {
+    /// new computes the edge weights of the target, colors being compared with the metric
+    pub fn new(target: &ImgRGBA, metric: Metric) -> EdgeWeighted {
+        let edges = sobel(target);
+        let strongest = edges.iter().cloned().fold(0.0, f32::max);
+
+        let mut weights: Vec<f32> = edges.iter()
+            .map(|&edge| if strongest > 0.0 { 1.0 + EDGE_GAIN * edge / strongest } else { 1.0 })
+            .collect();
+        let mean = weights.iter().sum::<f32>() / weights.len() as f32;
+        weights.iter_mut().for_each(|weight| *weight /= mean);
+
+        EdgeWeighted { reference: Reference::new(target.clone(), metric), weights }
+    }
+
+    /// weights returns the weight of each pixel, row by row
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+}
+
+impl Fitness for EdgeWeighted {
+    fn dimensions(&self) -> (u32, u32) {
+        self.reference.dimensions()
+    }
+
+    fn reach(&self) -> Option<u32> {
+        Some(0)
+    }
+
+    #[inline]
+    fn local_distance(&self, image: &ImgRGBA, x: u32, y: u32) -> f64 {
+        let weight = self.weights[(y * image.width() + x) as usize] as f64;
+        weight * self.reference.local_distance(image, x, y)
+    }
+}
+
+// sobel returns the gradient magnitude of the brightness of the image, row by
+// row, the pixels past the borders repeating the ones on the border
+pub(super) fn sobel(image: &ImgRGBA) -> Vec<f32> {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    let luma = |x: i64, y: i64| {
+        let pixel = image.get_pixel(x.clamp(0, width - 1) as u32, y.clamp(0, height - 1) as u32);
+        0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32
+    };
+
+    let mut edges = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let gx = luma(x + 1, y - 1) + 2.0 * luma(x + 1, y) + luma(x + 1, y + 1)
+                - luma(x - 1, y - 1) - 2.0 * luma(x - 1, y) - luma(x - 1, y + 1);
+            let gy = luma(x - 1, y + 1) + 2.0 * luma(x, y + 1) + luma(x + 1, y + 1)
+                - luma(x - 1, y - 1) - 2.0 * luma(x, y - 1) - luma(x + 1, y - 1);
+            edges.push((gx * gx + gy * gy).sqrt());
+        }
+    }
+    edges
+}
@@ -0,0 +1,292 @@
+use super::Fitness;
+use crate::ImgRGBA;
+
+// windows are 7x7 pixels around their center, cut at the borders of the image
+const RADIUS: u32 = 3;
+// constants keeping the ratios of SSIM stable when the means or variances are close to 0
+const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+// weights of the scales of MS-SSIM, from the full image to the smallest one
+const SCALE_WEIGHTS: [f64; 5] = [0.0448, 0.2856, 0.3001, 0.2363, 0.1333];
+
+/// Ssim is the structural similarity of 7x7 windows around each pixel, averaged
+/// over the RGB channels. The local distance is 50 * (1 - SSIM), from 0 to 100
+pub struct Ssim {
+    target: Plane,
+}
+
+impl Ssim {
+    /// new prepares the SSIM of images against the target
+    pub fn new(target: &ImgRGBA) -> Ssim {
+        Ssim { target: Plane::from_image(target) }
+    }
+}
+
+impl Fitness for Ssim {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.target.width, self.target.height)
+    }
+
+    fn reach(&self) -> Option<u32> {
+        Some(RADIUS)
+    }
+
+    fn local_distance(&self, image: &ImgRGBA, x: u32, y: u32) -> f64 {
+        let (l, cs) = components(&self.target, x, y, |x, y| rgb(image.get_pixel(x, y)));
+        50.0 * (1.0 - l * cs)
+    }
+}
+
+/// MsSsim is the multi-scale structural similarity, which combines the SSIM of the
+/// image halved again and again so large structures count as well as fine ones.
+/// The distance is 100 * (1 - MS-SSIM), from 0 to 100
+pub struct MsSsim {
+    // target halved once per scale, the full image first
+    scales: Vec<Plane>,
+}
+
+impl MsSsim {
+    /// new prepares the MS-SSIM of images against the target, with up to 5 scales
+    /// while the halved target stays larger than a window
+    pub fn new(target: &ImgRGBA) -> MsSsim {
+        let mut scales = vec![Plane::from_image(target)];
+        while scales.len() < SCALE_WEIGHTS.len() {
+            let last = &scales[scales.len() - 1];
+            if last.width / 2 <= 2 * RADIUS || last.height / 2 <= 2 * RADIUS {
+                break;
+            }
+            scales.push(last.halve());
+        }
+        MsSsim { scales }
+    }
+}
+
+impl Fitness for MsSsim {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.scales[0].width, self.scales[0].height)
+    }
+
+    fn reach(&self) -> Option<u32> {
+        None
+    }
+
+    // local_distance is the single scale SSIM, MS-SSIM isn't a mean of local values
+    fn local_distance(&self, image: &ImgRGBA, x: u32, y: u32) -> f64 {
+        let (l, cs) = components(&self.scales[0], x, y, |x, y| rgb(image.get_pixel(x, y)));
+        50.0 * (1.0 - l * cs)
+    }
+
+    fn distance(&self, image: &ImgRGBA) -> f64 {
+        // the weights of the scales in use are scaled so they still add up to 1
+        let weights = &SCALE_WEIGHTS[..self.scales.len()];
+        let total_weight: f64 = weights.iter().sum();
+
+        let mut plane = Plane::from_image(image);
+        let mut similarity = 1.0;
+        for (i, (target, weight)) in self.scales.iter().zip(weights).enumerate() {
+            if i > 0 {
+                plane = plane.halve();
+            }
+
+            // each scale reads every window, summed-area tables give their sums at once
+            let table = SummedArea::new(&plane, target);
+            let (mut l, mut cs) = (0.0, 0.0);
+            for y in 0..target.height {
+                for x in 0..target.width {
+                    let (x0, y0, x1, y1) = window(target, x, y);
+                    let (wl, wcs) = terms(&table.sums(x0, y0, x1, y1), ((x1 - x0 + 1) * (y1 - y0 + 1)) as f64);
+                    l += wl;
+                    cs += wcs;
+                }
+            }
+            let count = (target.width * target.height) as f64;
+            let (l, cs) = (l / count, cs / count);
+
+            // luminance only counts at the coarsest scale, negative terms would make the power undefined
+            let term = if i == self.scales.len() - 1 { l * cs } else { cs };
+            similarity *= term.max(0.0).powf(weight / total_weight);
+        }
+
+        100.0 * (1.0 - similarity)
+    }
+}
+
+// Plane is an image as RGB values, row by row
+struct Plane {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 3]>,
+}
+
+impl Plane {
+    fn from_image(image: &ImgRGBA) -> Plane {
+        Plane {
+            width: image.width(),
+            height: image.height(),
+            pixels: image.pixels().map(rgb).collect(),
+        }
+    }
+
+    #[inline]
+    fn get(&self, x: u32, y: u32) -> [f32; 3] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    // halve averages each block of 2x2 pixels
+    fn halve(&self) -> Plane {
+        let (width, height) = (self.width / 2, self.height / 2);
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let block = [self.get(2 * x, 2 * y), self.get(2 * x + 1, 2 * y), self.get(2 * x, 2 * y + 1), self.get(2 * x + 1, 2 * y + 1)];
+                pixels.push(std::array::from_fn(|c| block.iter().map(|p| p[c]).sum::<f32>() / 4.0));
+            }
+        }
+        Plane { width, height, pixels }
+    }
+}
+
+// Sums are the sums over a window of the image values, the target values,
+// their squares and their products, for each channel
+type Sums = [[f64; 5]; 3];
+
+// SummedArea holds the sums of the window from (0, 0) to each pixel, with a
+// row and a column of zeros first, so the sums of any window take 4 lookups
+struct SummedArea {
+    width: usize,
+    table: Vec<Sums>,
+}
+
+impl SummedArea {
+    fn new(image: &Plane, target: &Plane) -> SummedArea {
+        let width = image.width as usize + 1;
+        let mut table = vec![[[0f64; 5]; 3]; width * (image.height as usize + 1)];
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let i = (y as usize + 1) * width + x as usize + 1;
+                let mut sums = pixel_sums(image.get(x, y), target.get(x, y));
+                for (c, sums) in sums.iter_mut().enumerate() {
+                    for (k, sum) in sums.iter_mut().enumerate() {
+                        *sum += table[i - 1][c][k] + table[i - width][c][k] - table[i - width - 1][c][k];
+                    }
+                }
+                table[i] = sums;
+            }
+        }
+        SummedArea { width, table }
+    }
+
+    // sums returns the sums of the window from (x0, y0) to (x1, y1) included
+    #[inline]
+    fn sums(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> Sums {
+        let at = |x: u32, y: u32| &self.table[y as usize * self.width + x as usize];
+        let (a, b, c, d) = (at(x1 + 1, y1 + 1), at(x0, y1 + 1), at(x1 + 1, y0), at(x0, y0));
+        std::array::from_fn(|channel| std::array::from_fn(|k| a[channel][k] - b[channel][k] - c[channel][k] + d[channel][k]))
+    }
+}
+
+// rgb returns the color of a pixel premultiplied by its alpha, so transparent
+// pixels compare as black
+#[inline]
+fn rgb(pixel: &image::Rgba<u8>) -> [f32; 3] {
+    let color = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+    match pixel[3] {
+        255 => color,
+        alpha => color.map(|c| c * alpha as f32 / 255.0),
+    }
+}
+
+// window returns the corners (x0, y0, x1, y1) of the window around (x, y)
+#[inline]
+fn window(target: &Plane, x: u32, y: u32) -> (u32, u32, u32, u32) {
+    let (x0, x1) = (x.saturating_sub(RADIUS), (x + RADIUS).min(target.width - 1));
+    let (y0, y1) = (y.saturating_sub(RADIUS), (y + RADIUS).min(target.height - 1));
+    (x0, y0, x1, y1)
+}
+
+// pixel_sums returns the sums of a single pixel of the image and of the target
+#[inline]
+fn pixel_sums(p: [f32; 3], t: [f32; 3]) -> Sums {
+    std::array::from_fn(|c| {
+        let (p, t) = (p[c] as f64, t[c] as f64);
+        [p, t, p * p, t * t, p * t]
+    })
+}
+
+// components returns the luminance term and the contrast-structure term of SSIM
+// over the window around (x, y), between the image read with pixel and the target,
+// averaged over the channels
+#[inline]
+fn components<F: Fn(u32, u32) -> [f32; 3]>(target: &Plane, x: u32, y: u32, pixel: F) -> (f64, f64) {
+    let (x0, y0, x1, y1) = window(target, x, y);
+
+    let mut sums = [[0f64; 5]; 3];
+    for wy in y0..=y1 {
+        for wx in x0..=x1 {
+            for (sums, pixel) in sums.iter_mut().zip(pixel_sums(pixel(wx, wy), target.get(wx, wy))) {
+                sums.iter_mut().zip(pixel).for_each(|(sum, value)| *sum += value);
+            }
+        }
+    }
+
+    terms(&sums, ((x1 - x0 + 1) * (y1 - y0 + 1)) as f64)
+}
+
+// terms returns the luminance term and the contrast-structure term of SSIM
+// from the sums over a window of n pixels, averaged over the channels
+#[inline]
+fn terms(sums: &Sums, n: f64) -> (f64, f64) {
+    let (mut l, mut cs) = (0.0, 0.0);
+    for s in sums {
+        let (mp, mt) = (s[0] / n, s[1] / n);
+        let (vp, vt, cov) = (s[2] / n - mp * mp, s[3] / n - mt * mt, s[4] / n - mp * mt);
+        l += (2.0 * mp * mt + C1) / (mp * mp + mt * mt + C1);
+        cs += (2.0 * cov + C2) / (vp + vt + C2);
+    }
+    (l / 3.0, cs / 3.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    // target returns an image with a gradient and a few edges, for windows to compare
+    fn target() -> ImgRGBA {
+        ImgRGBA::from_fn(48, 40, |x, y| {
+            let stripe = if (x / 6 + y / 5) % 2 == 0 { 60 } else { 0 };
+            image::Rgba([(x * 4 + stripe) as u8, (y * 5) as u8, (120 + stripe) as u8, 255])
+        })
+    }
+
+    // noisy returns the target with every channel moved by up to amplitude, the
+    // same draws scaled for every amplitude
+    fn noisy(target: &ImgRGBA, amplitude: f64) -> ImgRGBA {
+        let mut rng = ChaCha8Rng::seed_from_u64(6);
+        let mut image = target.clone();
+        for pixel in image.pixels_mut() {
+            for channel in &mut pixel.0[..3] {
+                *channel = (*channel as f64 + amplitude * rng.gen_range(-1.0..1.0)).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn target_is_at_no_distance() {
+        let target = target();
+        assert_eq!(Ssim::new(&target).distance(&target), 0.0);
+        assert_eq!(MsSsim::new(&target).distance(&target), 0.0);
+    }
+
+    #[test]
+    fn distance_grows_with_noise() {
+        let target = target();
+        let fitnesses: [Box<dyn Fitness>; 2] = [Box::new(Ssim::new(&target)), Box::new(MsSsim::new(&target))];
+        for fitness in &fitnesses {
+            let distances: Vec<f64> = [0.0, 10.0, 30.0, 90.0].iter().map(|&amplitude| fitness.distance(&noisy(&target, amplitude))).collect();
+            assert!(distances.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", distances);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use super::edges::sobel;
+use super::Fitness;
+use crate::ImgRGBA;
+use image::imageops::{self, FilterType};
+use image::GrayImage;
+use serde::{Deserialize, Serialize};
+
+// share of the weight every pixel keeps under saliency, so flat areas still count
+const SALIENCY_FLOOR: f32 = 0.2;
+
+/// Weights selects how much each pixel of the target counts in the distance
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weights {
+    /// every pixel counts the same
+    #[default]
+    Uniform,
+    /// pixels count more in detailed areas of the target, found with the
+    /// magnitude of its gradient blurred over a few percent of the image
+    Saliency,
+    /// pixels count as much as the brightness of a grayscale mask of the
+    /// target size, row by row, black pixels not counting at all
+    Mask(Vec<u8>),
+}
+
+impl Weights {
+    /// weigh wraps the fitness of the target so the local distance of each pixel
+    /// is scaled by its weight, the weights having a mean of 1 so distances stay
+    /// comparable with unweighted ones
+    ///
+    /// Panics if the fitness isn't a mean of local distances or a mask isn't of the target size.
+    pub fn weigh(&self, fitness: Box<dyn Fitness>, target: &ImgRGBA) -> Box<dyn Fitness> {
+        let weights = match self {
+            Weights::Uniform => return fitness,
+            Weights::Saliency => saliency(target),
+            Weights::Mask(mask) => {
+                assert_eq!(mask.len(), (target.width() * target.height()) as usize, "the mask must be of the target size");
+                mask.iter().map(|&value| value as f32 / 255.0).collect()
+            }
+        };
+        assert!(fitness.reach().is_some(), "weights only apply to distances made of local distances");
+        Box::new(Weighted { fitness, weights: normalize(weights) })
+    }
+
+    /// resize returns the weights for a target resized from one size to another,
+    /// a mask being resized as a grayscale image
+    ///
+    /// Panics if a mask isn't of the first size.
+    pub fn resize(&self, from: (u32, u32), to: (u32, u32)) -> Weights {
+        match self {
+            Weights::Mask(mask) if from != to => {
+                let mask = GrayImage::from_raw(from.0, from.1, mask.clone()).expect("the mask must be of the target size");
+                Weights::Mask(imageops::resize(&mask, to.0, to.1, FilterType::Triangle).into_raw())
+            }
+            weights => weights.clone(),
+        }
+    }
+}
+
+// Weighted scales the local distances of a fitness by the weight of their pixel
+struct Weighted {
+    fitness: Box<dyn Fitness>,
+    weights: Vec<f32>,
+}
+
+impl Fitness for Weighted {
+    fn dimensions(&self) -> (u32, u32) {
+        self.fitness.dimensions()
+    }
+
+    fn reach(&self) -> Option<u32> {
+        self.fitness.reach()
+    }
+
+    #[inline]
+    fn local_distance(&self, image: &ImgRGBA, x: u32, y: u32) -> f64 {
+        let weight = self.weights[(y * image.width() + x) as usize] as f64;
+        weight * self.fitness.local_distance(image, x, y)
+    }
+}
+
+// normalize scales the weights to a mean of 1, weights all 0 become 1
+fn normalize(mut weights: Vec<f32>) -> Vec<f32> {
+    let mean = weights.iter().sum::<f32>() / weights.len() as f32;
+    if mean > 0.0 {
+        weights.iter_mut().for_each(|weight| *weight /= mean);
+    } else {
+        weights.fill(1.0);
+    }
+    weights
+}
+
+// saliency returns the gradient magnitude of the target blurred over 2% of its
+// largest side, scaled from SALIENCY_FLOOR to 1
+fn saliency(target: &ImgRGBA) -> Vec<f32> {
+    let (width, height) = (target.width() as usize, target.height() as usize);
+    let radius = (width.max(height) / 50).max(1);
+    let blurred = blur(&blur(&sobel(target), width, height, radius, 1), height, width, radius, width);
+
+    let strongest = blurred.iter().cloned().fold(0.0, f32::max);
+    blurred.iter()
+        .map(|&value| if strongest > 0.0 { SALIENCY_FLOOR + (1.0 - SALIENCY_FLOOR) * value / strongest } else { 1.0 })
+        .collect()
+}
+
+// blur averages each value with the ones up to radius away on its line, the
+// lines being the rows when step is 1 or the columns when step is the width,
+// each one `len` values long and the window being cut at its ends
+fn blur(values: &[f32], len: usize, lines: usize, radius: usize, step: usize) -> Vec<f32> {
+    let mut blurred = vec![0.0; values.len()];
+    for line in 0..lines {
+        let start = if step == 1 { line * len } else { line };
+        let at = |i: usize| start + i * step;
+
+        let mut sum: f32 = (0..radius.min(len - 1) + 1).map(|i| values[at(i)]).sum();
+        for i in 0..len {
+            let (first, last) = (i.saturating_sub(radius), (i + radius).min(len - 1));
+            blurred[at(i)] = sum / (last - first + 1) as f32;
+            if i + radius + 1 < len {
+                sum += values[at(i + radius + 1)];
+            }
+            if i >= radius {
+                sum -= values[at(i - radius)];
+            }
+        }
+    }
+    blurred
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fitness::{Metric, Reference};
+    use image::Rgba;
+
+    // halves returns a mask of the given size, black on the left half and white on the right one
+    fn halves(width: u32, height: u32) -> Vec<u8> {
+        GrayImage::from_fn(width, height, |x, _| image::Luma([if x < width / 2 { 0 } else { 255 }])).into_raw()
+    }
+
+    // blotted returns a gray image with a white square on the given column of 2 pixels
+    fn blotted(width: u32, height: u32, x: u32) -> ImgRGBA {
+        ImgRGBA::from_fn(width, height, |px, py| {
+            if px / 2 == x / 2 && py < 2 { Rgba([255, 255, 255, 255]) } else { Rgba([128, 128, 128, 255]) }
+        })
+    }
+
+    #[test]
+    fn normalize_scales_to_a_mean_of_one() {
+        assert_eq!(normalize(vec![1.0, 2.0, 3.0, 2.0]), [0.5, 1.0, 1.5, 1.0]);
+        assert_eq!(normalize(vec![0.0; 3]), [1.0; 3]);
+    }
+
+    #[test]
+    fn mask_weighs_the_local_distances() {
+        let target = ImgRGBA::from_pixel(8, 4, Rgba([128, 128, 128, 255]));
+        let plain = Reference::new(target.clone(), Metric::Rgb);
+        let weighted = Weights::Mask(halves(8, 4)).weigh(Box::new(Reference::new(target.clone(), Metric::Rgb)), &target);
+
+        // black pixels don't count, white ones count twice so the mean weight stays 1
+        assert_eq!(weighted.distance(&blotted(8, 4, 0)), 0.0);
+        let image = blotted(8, 4, 6);
+        assert!((weighted.distance(&image) - 2.0 * plain.distance(&image)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn uniform_weights_leave_the_fitness_alone() {
+        let target = ImgRGBA::from_pixel(8, 4, Rgba([128, 128, 128, 255]));
+        let weighted = Weights::Uniform.weigh(Box::new(Reference::new(target.clone(), Metric::Rgb)), &target);
+        let image = blotted(8, 4, 2);
+        assert_eq!(weighted.distance(&image), Reference::new(target, Metric::Rgb).distance(&image));
+    }
+
+    #[test]
+    fn resize_follows_the_target() {
+        let Weights::Mask(mask) = Weights::Mask(halves(16, 8)).resize((16, 8), (8, 4)) else {
+            panic!("a mask stays a mask");
+        };
+        assert_eq!(mask.len(), 8 * 4);
+        // the middle columns blend both halves, the outer ones keep their value
+        for row in mask.chunks(8) {
+            assert_eq!((row[0], row[7]), (0, 255));
+        }
+
+        let mask = Weights::Mask(halves(16, 8));
+        assert_eq!(mask.resize((16, 8), (16, 8)), mask);
+        assert_eq!(Weights::Saliency.resize((16, 8), (8, 4)), Weights::Saliency);
+    }
+
+    #[test]
+    fn saliency_is_flat_on_a_flat_target() {
+        let target = ImgRGBA::from_pixel(8, 4, Rgba([10, 200, 30, 255]));
+        assert_eq!(saliency(&target), [1.0; 32]);
+    }
+}
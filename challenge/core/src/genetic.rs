@@ -0,0 +1,329 @@
+use crate::draw::draw;
+use crate::fitness::Fitness;
+use crate::mutate::{mutate, Mutation, Operators};
+use crate::shape::Primitive;
+use crate::ImgRGBA;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// Genetic holds the parameters of the genetic algorithm, which evolves a
+/// population of genomes, each one a full set of shapes, instead of a single one
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Genetic {
+    /// number of genomes in each generation
+    pub population: usize,
+    /// how parents are picked
+    pub selection: Selection,
+    /// number of genomes competing in each tournament
+    pub tournament: usize,
+    /// how the shapes of 2 parents are spliced into a child
+    pub crossover: Crossover,
+    /// probability that a child is spliced from 2 parents instead of copied from one
+    pub crossover_rate: f64,
+    /// number of the best genomes copied unchanged into the next generation, less than the population
+    pub elitism: usize,
+}
+
+impl Default for Genetic {
+    fn default() -> Self {
+        Genetic {
+            population: 50,
+            selection: Selection::Tournament,
+            tournament: 3,
+            crossover: Crossover::OnePoint,
+            crossover_rate: 0.9,
+            elitism: 2,
+        }
+    }
+}
+
+/// Selection is the way parents are picked from a generation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+    /// the closest of a few genomes picked at random
+    Tournament,
+    /// a genome picked with a probability growing with how much closer
+    /// to the target it is than the worst genome
+    Roulette,
+}
+
+/// Crossover is the way the shapes of 2 parents are spliced, a child always
+/// takes the shape at each position in the stacking order from one of them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum Crossover {
+    /// the shapes of the first parent up to a random position, then the ones of the second
+    OnePoint,
+    /// the shapes of the second parent between 2 random positions, the ones of the first elsewhere
+    TwoPoint,
+    /// each shape from either parent at random
+    Uniform,
+}
+
+// Genome is a member of the population with its distance to the target
+#[derive(Clone, Debug)]
+pub(crate) struct Genome {
+    pub(crate) shapes: Vec<Primitive>,
+    pub(crate) distance: f64,
+}
+
+// Variation holds how children are mutated, as in the hill climber
+pub(crate) struct Variation {
+    pub(crate) operators: Operators,
+    pub(crate) mutation: Mutation,
+    pub(crate) sigma: f64,
+}
+
+// evaluate draws the shapes of each genome on the canvas and computes its distance to the target
+pub(crate) fn evaluate(target: &dyn Fitness, canvas: &ImgRGBA, shapes: Vec<Vec<Primitive>>) -> Vec<Genome> {
+    let mut image = canvas.clone();
+    shapes
+        .into_iter()
+        .map(|shapes| {
+            image.clone_from(canvas);
+            draw(&mut image, &shapes);
+            Genome { distance: target.distance(&image), shapes }
+        })
+        .collect()
+}
+
+// sort orders the genomes from the closest to the target, keeping the order of equal ones
+pub(crate) fn sort(genomes: &mut [Genome]) {
+    genomes.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+}
+
+// breed returns the children of a sorted generation, one for each genome that
+// isn't in the elite: selected parents are spliced then mutated once
+pub(crate) fn breed<R: RngCore>(
+    rng: &mut R,
+    (width, height): (u32, u32),
+    generation: &[Genome],
+    genetic: &Genetic,
+    variation: &Variation,
+) -> Vec<Vec<Primitive>> {
+    let size = genetic.population.max(2);
+    (genetic.elitism.min(size)..size)
+        .map(|_| {
+            let first = select(rng, generation, genetic);
+            let mut child = if rng.gen_bool(genetic.crossover_rate.clamp(0.0, 1.0)) {
+                let second = select(rng, generation, genetic);
+                crossover(rng, &first.shapes, &second.shapes, genetic.crossover)
+            } else {
+                first.shapes.clone()
+            };
+
+            let (index, shape, _) = mutate(
+                rng, &child, width, height,
+                &variation.operators, variation.mutation, variation.sigma,
+            );
+            child[index] = shape;
+            child
+        })
+        .collect()
+}
+
+// next_generation keeps the elite of a sorted generation and adds the evaluated children
+pub(crate) fn next_generation(generation: &[Genome], genetic: &Genetic, children: Vec<Genome>) -> Vec<Genome> {
+    let elitism = genetic.elitism.min(genetic.population.max(2));
+    let mut next: Vec<Genome> = generation[..elitism.min(generation.len())].to_vec();
+    next.extend(children);
+    sort(&mut next);
+    next
+}
+
+// select picks a parent in the generation
+fn select<'a, R: RngCore>(rng: &mut R, generation: &'a [Genome], genetic: &Genetic) -> &'a Genome {
+    match genetic.selection {
+        Selection::Tournament => (0..genetic.tournament.max(1))
+            .map(|_| &generation[rng.gen_range(0..generation.len())])
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+            .unwrap(),
+        Selection::Roulette => {
+            let worst = generation.iter().map(|genome| genome.distance).fold(f64::MIN, f64::max);
+            let total: f64 = generation.iter().map(|genome| worst - genome.distance).sum();
+            if total <= 0.0 {
+                return &generation[rng.gen_range(0..generation.len())];
+            }
+
+            let mut ball = rng.gen_range(0.0..total);
+            for genome in generation {
+                ball -= worst - genome.distance;
+                if ball < 0.0 {
+                    return genome;
+                }
+            }
+            &generation[0]
+        }
+    }
+}
+
+// crossover splices the shapes of 2 parents of the same size into a child
+fn crossover<R: RngCore>(rng: &mut R, first: &[Primitive], second: &[Primitive], crossover: Crossover) -> Vec<Primitive> {
+    let len = first.len();
+    if len < 2 {
+        return first.to_vec();
+    }
+
+    match crossover {
+        Crossover::OnePoint => {
+            let cut = rng.gen_range(1..len);
+            first[..cut].iter().chain(&second[cut..]).cloned().collect()
+        }
+        Crossover::TwoPoint => {
+            let (a, b) = (rng.gen_range(0..len), rng.gen_range(0..len));
+            let (start, end) = (a.min(b), a.max(b) + 1);
+            first[..start].iter().chain(&second[start..end]).chain(&first[end..]).cloned().collect()
+        }
+        Crossover::Uniform => first.iter().zip(second)
+            .map(|(a, b)| if rng.gen() { a.clone() } else { b.clone() })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutate::ColorMutation;
+    use crate::shape::{Point, Shape, Triangle};
+    use image::Rgba;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    // parent returns 8 triangles told apart by their color, the red channel
+    // naming the parent and the green one the position
+    fn parent(name: u8) -> Vec<Primitive> {
+        (0..8)
+            .map(|i| {
+                let points = [Point { x: 0, y: 0 }, Point { x: 4, y: 0 }, Point { x: 0, y: 4 }];
+                Primitive::Triangle(Triangle { points, color: Rgba([name, i, 0, 255]) })
+            })
+            .collect()
+    }
+
+    // origins returns which parent each shape of a child comes from, checking
+    // that it kept its position
+    fn origins(child: &[Primitive]) -> Vec<u8> {
+        child
+            .iter()
+            .enumerate()
+            .map(|(i, shape)| {
+                let color = shape.color();
+                assert_eq!(color[1] as usize, i, "a shape moved");
+                color[0]
+            })
+            .collect()
+    }
+
+    // children returns the origins of many children of the same 2 parents
+    fn children(crossover: Crossover) -> Vec<Vec<u8>> {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let (first, second) = (parent(1), parent(2));
+        (0..500).map(|_| origins(&super::crossover(&mut rng, &first, &second, crossover))).collect()
+    }
+
+    // runs returns the number of runs of shapes from the same parent
+    fn runs(origins: &[u8]) -> usize {
+        1 + origins.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    }
+
+    fn generation(distances: &[f64]) -> Vec<Genome> {
+        distances.iter().enumerate().map(|(i, &distance)| Genome { shapes: parent(i as u8), distance }).collect()
+    }
+
+    // picks returns how many times each genome of the generation is selected
+    fn picks(generation: &[Genome], genetic: &Genetic) -> Vec<usize> {
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        let mut picks = vec![0; generation.len()];
+        for _ in 0..9000 {
+            picks[select(&mut rng, generation, genetic).shapes[0].color()[0] as usize] += 1;
+        }
+        picks
+    }
+
+    #[test]
+    fn one_point_crossover_takes_the_head_of_the_first_parent() {
+        for origins in children(Crossover::OnePoint) {
+            assert_eq!(origins[0], 1);
+            assert_eq!(origins[7], 2);
+            assert_eq!(runs(&origins), 2, "{:?}", origins);
+        }
+    }
+
+    #[test]
+    fn two_point_crossover_takes_a_run_of_the_second_parent() {
+        for origins in children(Crossover::TwoPoint) {
+            let second: Vec<usize> = (0..8).filter(|&i| origins[i] == 2).collect();
+            assert!(!second.is_empty());
+            // the shapes of the second parent are contiguous
+            assert_eq!(second.len(), second[second.len() - 1] - second[0] + 1, "{:?}", origins);
+        }
+    }
+
+    #[test]
+    fn uniform_crossover_mixes_both_parents_everywhere() {
+        let children = children(Crossover::Uniform);
+        for i in 0..8 {
+            let from_first = children.iter().filter(|origins| origins[i] == 1).count();
+            assert!((200..300).contains(&from_first), "position {} took {} of 500 from the first parent", i, from_first);
+        }
+    }
+
+    #[test]
+    fn crossover_copies_a_parent_too_short_to_splice() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let (first, second) = (parent(1)[..1].to_vec(), parent(2)[..1].to_vec());
+        assert_eq!(crossover(&mut rng, &first, &second, Crossover::Uniform), first);
+    }
+
+    #[test]
+    fn tournament_favors_the_closest_genomes() {
+        let generation = generation(&[1.0, 2.0, 3.0, 4.0]);
+        let genetic = Genetic { tournament: 3, ..Genetic::default() };
+        let picks = picks(&generation, &genetic);
+        assert!(picks.windows(2).all(|pair| pair[0] > pair[1]), "{:?}", picks);
+        // the farthest genome only wins a tournament of itself alone
+        assert!(picks[3] > 0 && picks[3] < 9000 / 32, "{:?}", picks);
+
+        // a tournament of one is a uniform draw
+        let picks = self::picks(&generation, &Genetic { tournament: 1, ..Genetic::default() });
+        assert!(picks.iter().all(|&count| (2000..2500).contains(&count)), "{:?}", picks);
+    }
+
+    #[test]
+    fn roulette_draws_by_the_distance_to_the_worst() {
+        let generation = generation(&[1.0, 2.0, 4.0, 6.0]);
+        let genetic = Genetic { selection: Selection::Roulette, ..Genetic::default() };
+        let picks = picks(&generation, &genetic);
+        // the weights are 5, 4 and 2 out of 11, the worst genome has none
+        for (count, weight) in picks.iter().zip([5.0, 4.0, 2.0, 0.0]) {
+            let expected = 9000.0 * weight / 11.0;
+            assert!((*count as f64 - expected).abs() < 200.0, "{:?}", picks);
+        }
+        assert_eq!(picks[3], 0);
+
+        // equal genomes are drawn uniformly
+        let picks = self::picks(&self::generation(&[3.0; 4]), &genetic);
+        assert!(picks.iter().all(|&count| (2000..2500).contains(&count)), "{:?}", picks);
+    }
+
+    #[test]
+    fn next_generation_keeps_the_elite() {
+        let genetic = Genetic { population: 5, elitism: 2, ..Genetic::default() };
+        let previous = generation(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        let variation = Variation { operators: Operators::from(ColorMutation::Channel), mutation: Mutation::Uniform, sigma: 0.05 };
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let children = breed(&mut rng, (40, 30), &previous, &genetic, &variation);
+        assert_eq!(children.len(), 3);
+
+        // the elite stays ahead of worse children and behind better ones
+        let next = next_generation(&previous, &genetic, generation(&[0.5, 6.0, 7.0]));
+        let distances: Vec<f64> = next.iter().map(|genome| genome.distance).collect();
+        assert_eq!(distances, [0.5, 1.0, 2.0, 6.0, 7.0]);
+        assert_eq!(next[1].shapes, previous[0].shapes);
+        assert_eq!(next[2].shapes, previous[1].shapes);
+    }
+}
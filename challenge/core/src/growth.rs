@@ -0,0 +1,132 @@
+use crate::shape::{random_shape, BoundingBox, Primitive, Shape, ShapeKind};
+use crate::ImgRGBA;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// the image is cut in GRID by GRID cells to find where it is farthest from the target
+const GRID: u32 = 8;
+
+/// Growth starts a run with a few shapes and adds one whenever the run stops
+/// getting closer to the target, up to the number of shapes of the config,
+/// so shapes aren't spent on random noise at the start
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Growth {
+    /// number of shapes the run starts with
+    pub start: usize,
+    /// number of epochs in a row without a better distance after which a shape is added
+    pub patience: u64,
+}
+
+// largest_error returns the cell of a grid of 8 by 8 cells of a width*height
+// image where the sum of the local distances to the target is the largest
+pub(crate) fn largest_error(width: u32, height: u32, local_distance: impl Fn(u32, u32) -> f64) -> BoundingBox {
+    let mut errors = vec![0.0; (GRID * GRID) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            errors[(y * GRID / height * GRID + x * GRID / width) as usize] += local_distance(x, y);
+        }
+    }
+
+    let mut cell = 0;
+    for (i, &error) in errors.iter().enumerate() {
+        if error > errors[cell] {
+            cell = i;
+        }
+    }
+    let (column, row) = (cell as u32 % GRID, cell as u32 / GRID);
+    BoundingBox {
+        xmin: (column * width / GRID) as i32,
+        ymin: (row * height / GRID) as i32,
+        xmax: ((column + 1) * width / GRID) as i32,
+        ymax: ((row + 1) * height / GRID) as i32,
+    }
+}
+
+// shape_in returns a random shape of one of the kinds that fits in the area,
+// with the mean color of the target there and a random alpha
+pub(crate) fn shape_in<R: Rng>(
+    rng: &mut R,
+    area: BoundingBox,
+    target: &ImgRGBA,
+    kinds: &[ShapeKind],
+    vertices: usize,
+    convex: bool,
+) -> Primitive {
+    let (width, height) = target.dimensions();
+    let (area_width, area_height) = ((area.xmax - area.xmin) as u32, (area.ymax - area.ymin) as u32);
+
+    let kind = kinds[rng.gen_range(0..kinds.len())];
+    let mut shape = random_shape(rng, area_width, area_height, kind, vertices, convex);
+    shape.translate(area.xmin, area.ymin, width, height);
+
+    let mut sum = [0u64; 3];
+    for y in area.ymin..area.ymax {
+        for x in area.xmin..area.xmax {
+            let pixel = target.get_pixel(x as u32, y as u32);
+            sum.iter_mut().zip(pixel.0).for_each(|(sum, channel)| *sum += channel as u64);
+        }
+    }
+    let count = (area_width as u64 * area_height as u64).max(1);
+    let color = shape.color_mut();
+    for (channel, sum) in color.0.iter_mut().zip(sum) {
+        *channel = (sum / count) as u8;
+    }
+    shape
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{evaluator, Backend};
+    use crate::fitness::{Fitness, Metric, Reference};
+    use image::Rgba;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    // cell is the one of column 5 and row 2 of a 40x40 image, 5 pixels wide
+    const CELL: BoundingBox = BoundingBox { xmin: 25, ymin: 10, xmax: 30, ymax: 15 };
+
+    // images returns a gray target, and an image far from it in the cell and slightly off at a pixel elsewhere
+    fn images() -> (ImgRGBA, ImgRGBA) {
+        let target = ImgRGBA::from_pixel(40, 40, Rgba([128, 128, 128, 255]));
+        let mut image = target.clone();
+        image.put_pixel(3, 3, Rgba([140, 128, 128, 255]));
+        for y in CELL.ymin..CELL.ymax {
+            for x in CELL.xmin..CELL.xmax {
+                image.put_pixel(x as u32, y as u32, Rgba([255, 0, 0, 255]));
+            }
+        }
+        (target, image)
+    }
+
+    #[test]
+    fn largest_error_is_the_worst_cell() {
+        let (target, image) = images();
+        let fitness = Reference::new(target, Metric::Rgb);
+        assert_eq!(largest_error(40, 40, |x, y| fitness.local_distance(&image, x, y)), CELL);
+
+        // the layered backend measures the same local distances
+        let mut layered = evaluator(Backend::Layered);
+        layered.reset(&fitness, &image, &[]);
+        let distances = layered.local_distances().unwrap();
+        assert_eq!(largest_error(40, 40, |x, y| distances[(y * 40 + x) as usize]), CELL);
+        assert!(evaluator(Backend::Full).local_distances().is_none());
+    }
+
+    #[test]
+    fn new_shape_lands_in_the_cell() {
+        let (target, _) = images();
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        for kind in [ShapeKind::Triangle, ShapeKind::Polygon, ShapeKind::Circle, ShapeKind::Ellipse, ShapeKind::Rectangle, ShapeKind::RotatedRectangle] {
+            for _ in 0..20 {
+                let shape = shape_in(&mut rng, CELL, &target, &[kind], 5, false);
+                // the shape is drawn in the cell, though its edges may spill over
+                let bounds = shape.bounding_box();
+                let center = ((bounds.xmin + bounds.xmax) / 2, (bounds.ymin + bounds.ymax) / 2);
+                assert!((CELL.xmin..=CELL.xmax).contains(&center.0), "{:?}", shape);
+                assert!((CELL.ymin..=CELL.ymax).contains(&center.1), "{:?}", shape);
+                assert_eq!(&shape.color().0[..3], &[128, 128, 128]);
+            }
+        }
+    }
+}
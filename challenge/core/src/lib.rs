@@ -0,0 +1,52 @@
+//! Approximation engine shared by the `evolve` and `upgrade` binaries.
+//!
+//! An [`Evolver`] keeps a set of semi-transparent shapes (triangles, polygons,
+//! circles, ellipses or rectangles) stacked on a background, white by
+//! default. Every [`Evolver::step`] mutates one shape and keeps the mutation
+//! only if the rendered image gets closer to the target image.
+//!
+//! ```no_run
+//! use evolve_core::{Config, Evolver};
+//!
+//! let target = image::open("target.png").unwrap().into_rgba8();
+//! let mut evolver = Evolver::new(target, Config::default());
+//! evolver.run(10_000);
+//! evolver.render().save("output.png").unwrap();
+//! ```
+
+pub mod acceptance;
+pub mod background;
+mod backend;
+pub mod checkpoint;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod draw;
+mod evolver;
+pub mod fitness;
+pub mod genetic;
+pub mod growth;
+pub mod mutate;
+pub mod progress;
+pub mod shape;
+pub mod stop;
+pub mod svg;
+pub mod timelapse;
+
+pub use acceptance::{Acceptance, Annealing, Schedule};
+pub use background::{Background, Canvas};
+pub use backend::Backend;
+pub use checkpoint::{Checkpoint, CheckpointError};
+pub use evolver::{Config, Evolver, Step};
+pub use fitness::{EdgeWeighted, Fitness, Metric, MsSsim, Objective, Reference, Ssim, Weights};
+pub use genetic::{Crossover, Genetic, Selection};
+pub use growth::Growth;
+pub use mutate::{ColorMutation, Mutation, MutationKind, Operator, OperatorStats, Operators, StepSize, Structure};
+pub use progress::{LogFormat, ProgressLog, Record};
+pub use shape::{
+    BoundingBox, Circle, Ellipse, Point, Polygon, Primitive, Rectangle, RotatedRectangle, Shape, ShapeKind, Triangle,
+};
+pub use stop::{Stop, StopReason, Watch};
+pub use timelapse::{Timelapse, TimelapseError};
+
+// type alias, so we can use the type ImgRGBA instead of ImageBuffer<Rgba<u8>, Vec<u8>>
+pub type ImgRGBA = image::ImageBuffer<image::Rgba<u8>, Vec<u8>>;
@@ -0,0 +1,652 @@
+use crate::shape::{gaussian, nudge, random_point, Primitive, Shape};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// ColorMutation selects how the color of a shape is mutated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMutation {
+    /// replaces the whole RGBA color, as the baseline does
+    Replace,
+    /// replaces a single RGBA channel, as the upgrade does
+    Channel,
+}
+
+/// Mutation selects how far a mutation moves a shape
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum Mutation {
+    /// replaces coordinates and channels with uniformly random values
+    #[default]
+    Uniform,
+    /// nudges coordinates and channels by a Gaussian step, whose size
+    /// adapts to the acceptance rate with StepSize
+    Gaussian,
+}
+
+/// MutationKind is what a mutation changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationKind {
+    /// the vertices, position or size of a shape
+    Geometry,
+    /// the color of a shape
+    Color,
+    /// the colors of the background
+    Background,
+    /// a whole generation of the genetic algorithm
+    Generation,
+    /// 2 shapes traded places in the stacking order
+    Swap,
+    /// a shape moved on top of the others
+    ToTop,
+    /// a shape moved under the others
+    ToBottom,
+    /// a shape was removed
+    Delete,
+    /// a random shape was added
+    Insert,
+}
+
+impl fmt::Display for MutationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MutationKind::Geometry => "geometry",
+            MutationKind::Color => "color",
+            MutationKind::Background => "background",
+            MutationKind::Generation => "generation",
+            MutationKind::Swap => "swap",
+            MutationKind::ToTop => "to_top",
+            MutationKind::ToBottom => "to_bottom",
+            MutationKind::Delete => "delete",
+            MutationKind::Insert => "insert",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Operator is a way of mutating a shape
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    /// moves a vertex, or changes whatever else defines the geometry of the shape
+    Vertex,
+    /// moves the whole shape to a random place
+    Move,
+    /// changes a single RGBA channel
+    Channel,
+    /// changes the whole RGBA color
+    Color,
+    /// changes the opacity only
+    Alpha,
+    /// moves the whole shape by a Gaussian step
+    Translate,
+    /// grows or shrinks the shape around its center
+    Scale,
+    /// turns the shape around its center
+    Rotate,
+}
+
+impl Operator {
+    /// ALL lists the operators in the order of their declaration
+    pub const ALL: [Operator; 8] = [
+        Operator::Vertex,
+        Operator::Move,
+        Operator::Channel,
+        Operator::Color,
+        Operator::Alpha,
+        Operator::Translate,
+        Operator::Scale,
+        Operator::Rotate,
+    ];
+
+    /// kind returns what the operator changes
+    pub fn kind(self) -> MutationKind {
+        match self {
+            Operator::Channel | Operator::Color | Operator::Alpha => MutationKind::Color,
+            _ => MutationKind::Geometry,
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operator::Vertex => "vertex",
+            Operator::Move => "move",
+            Operator::Channel => "channel",
+            Operator::Color => "color",
+            Operator::Alpha => "alpha",
+            Operator::Translate => "translate",
+            Operator::Scale => "scale",
+            Operator::Rotate => "rotate",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Operators holds the weights of the operators, each mutation of a shape
+/// picking one with a probability proportional to its weight
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Operators {
+    pub vertex: u32,
+    pub r#move: u32,
+    pub channel: u32,
+    pub color: u32,
+    pub alpha: u32,
+    pub translate: u32,
+    pub scale: u32,
+    pub rotate: u32,
+}
+
+impl Operators {
+    /// weight returns the weight of an operator
+    pub fn weight(&self, operator: Operator) -> u32 {
+        match operator {
+            Operator::Vertex => self.vertex,
+            Operator::Move => self.r#move,
+            Operator::Channel => self.channel,
+            Operator::Color => self.color,
+            Operator::Alpha => self.alpha,
+            Operator::Translate => self.translate,
+            Operator::Scale => self.scale,
+            Operator::Rotate => self.rotate,
+        }
+    }
+
+    /// weight_mut returns the weight of an operator, to be changed
+    pub fn weight_mut(&mut self, operator: Operator) -> &mut u32 {
+        match operator {
+            Operator::Vertex => &mut self.vertex,
+            Operator::Move => &mut self.r#move,
+            Operator::Channel => &mut self.channel,
+            Operator::Color => &mut self.color,
+            Operator::Alpha => &mut self.alpha,
+            Operator::Translate => &mut self.translate,
+            Operator::Scale => &mut self.scale,
+            Operator::Rotate => &mut self.rotate,
+        }
+    }
+
+    /// total returns the sum of the weights
+    pub fn total(&self) -> u32 {
+        Operator::ALL.iter().map(|&operator| self.weight(operator)).sum()
+    }
+
+    /// pick draws an operator with a probability proportional to its weight
+    ///
+    /// Panics if every weight is 0.
+    pub fn pick<R: RngCore + ?Sized>(&self, rng: &mut R) -> Operator {
+        // the color operators are walked first, so the weights of a color
+        // mutation draw the same operators as the former 50/50 split
+        let order = [
+            Operator::Channel,
+            Operator::Color,
+            Operator::Alpha,
+            Operator::Vertex,
+            Operator::Move,
+            Operator::Translate,
+            Operator::Scale,
+            Operator::Rotate,
+        ];
+        let mut draw = rng.gen_range(0..self.total());
+        for operator in order {
+            if draw < self.weight(operator) {
+                return operator;
+            }
+            draw -= self.weight(operator);
+        }
+        unreachable!("the draw is below the total of the weights")
+    }
+}
+
+impl From<ColorMutation> for Operators {
+    // a color mutation weighs the vertices and the color as much
+    fn from(color_mutation: ColorMutation) -> Operators {
+        match color_mutation {
+            ColorMutation::Replace => Operators { vertex: 1, color: 1, ..Operators::default() },
+            ColorMutation::Channel => Operators { vertex: 1, channel: 1, ..Operators::default() },
+        }
+    }
+}
+
+/// OperatorStats counts the mutations tried by each operator and the ones that were kept.
+/// With several threads, only the closest proposal of each epoch counts as tried
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperatorStats {
+    tried: [u64; Operator::ALL.len()],
+    kept: [u64; Operator::ALL.len()],
+}
+
+impl OperatorStats {
+    /// tried returns the number of mutations tried by an operator
+    pub fn tried(&self, operator: Operator) -> u64 {
+        self.tried[operator as usize]
+    }
+
+    /// kept returns the number of mutations of an operator that were kept
+    pub fn kept(&self, operator: Operator) -> u64 {
+        self.kept[operator as usize]
+    }
+
+    // count_tried counts a mutation tried by an operator
+    pub(crate) fn count_tried(&mut self, operator: Operator) {
+        self.tried[operator as usize] += 1;
+    }
+
+    // count_kept counts a mutation of an operator that was kept
+    pub(crate) fn count_kept(&mut self, operator: Operator) {
+        self.kept[operator as usize] += 1;
+    }
+}
+
+/// Structure holds the probabilities of the mutations that change the stacking
+/// order or the number of shapes, instead of a shape. They add up to at most 1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Structure {
+    /// trade the places of 2 shapes
+    pub swap: f64,
+    /// move a shape on top of the others
+    pub to_top: f64,
+    /// move a shape under the others
+    pub to_bottom: f64,
+    /// remove a shape, keeping at least one
+    pub delete: f64,
+    /// add a random shape at a random place, up to the number of shapes of the run
+    pub insert: f64,
+}
+
+impl Structure {
+    /// is_none tells whether no structural mutation can happen
+    pub fn is_none(&self) -> bool {
+        self.total() == 0.0
+    }
+
+    /// total returns the probability of a structural mutation
+    pub fn total(&self) -> f64 {
+        self.swap + self.to_top + self.to_bottom + self.delete + self.insert
+    }
+
+    /// pick draws a structural mutation with their probabilities, None standing
+    /// for a mutation of a shape. It doesn't draw anything when none can happen
+    pub fn pick<R: RngCore>(&self, rng: &mut R) -> Option<MutationKind> {
+        if self.is_none() {
+            return None;
+        }
+
+        let mut draw = rng.gen::<f64>();
+        let kinds = [
+            (self.swap, MutationKind::Swap),
+            (self.to_top, MutationKind::ToTop),
+            (self.to_bottom, MutationKind::ToBottom),
+            (self.delete, MutationKind::Delete),
+            (self.insert, MutationKind::Insert),
+        ];
+        for (probability, kind) in kinds {
+            if draw < probability {
+                return Some(kind);
+            }
+            draw -= probability;
+        }
+        None
+    }
+}
+
+/// restructure applies a structural mutation to a copy of the shapes and returns
+/// it with the index of the lowest layer that changed, or None when the mutation
+/// can't apply: a single shape can't be moved or deleted, and no shape is inserted
+/// once there are max of them. insert creates the shape to insert
+pub fn restructure<R: RngCore>(
+    rng: &mut R,
+    shapes: &[Primitive],
+    kind: MutationKind,
+    max: usize,
+    insert: impl FnOnce(&mut R) -> Primitive,
+) -> Option<(Vec<Primitive>, usize)> {
+    let len = shapes.len();
+    let mut shapes = shapes.to_vec();
+    let index = match kind {
+        MutationKind::Swap if len > 1 => {
+            let first = rng.gen_range(0..len);
+            let second = (first + rng.gen_range(1..len)) % len;
+            shapes.swap(first, second);
+            first.min(second)
+        }
+        MutationKind::ToTop if len > 1 => {
+            let index = rng.gen_range(0..len - 1);
+            let shape = shapes.remove(index);
+            shapes.push(shape);
+            index
+        }
+        MutationKind::ToBottom if len > 1 => {
+            let index = rng.gen_range(1..len);
+            let shape = shapes.remove(index);
+            shapes.insert(0, shape);
+            0
+        }
+        MutationKind::Delete if len > 1 => {
+            let index = rng.gen_range(0..len);
+            shapes.remove(index);
+            index
+        }
+        MutationKind::Insert if len < max => {
+            let index = rng.gen_range(0..=len);
+            shapes.insert(index, insert(rng));
+            index
+        }
+        _ => return None,
+    };
+    Some((shapes, index))
+}
+
+// the 1/5th success rule looks at the acceptance rate every ADAPT_EVERY
+// attempts and multiplies the step size by ADAPT_FACTOR or divides it by ADAPT_FACTOR
+const ADAPT_EVERY: u32 = 50;
+const ADAPT_FACTOR: f64 = 0.85;
+const MIN_SIGMA: f64 = 1e-3;
+const MAX_SIGMA: f64 = 0.5;
+
+/// StepSize is the standard deviation of the Gaussian mutations, as a fraction of
+/// the largest side of the image for coordinates and of 255 for color channels.
+/// It follows the 1/5th success rule: it grows when more than a fifth of the
+/// recent mutations were accepted, since the search can afford bigger jumps,
+/// and shrinks otherwise to refine the shapes
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StepSize {
+    sigma: f64,
+    attempts: u32,
+    accepts: u32,
+}
+
+impl StepSize {
+    /// new creates a step size starting at sigma
+    pub fn new(sigma: f64) -> StepSize {
+        StepSize { sigma: sigma.clamp(MIN_SIGMA, MAX_SIGMA), attempts: 0, accepts: 0 }
+    }
+
+    /// sigma returns the current standard deviation
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+
+    /// update records whether a mutation was accepted and adapts the step size
+    /// once enough mutations were tried
+    pub fn update(&mut self, accepted: bool) {
+        self.attempts += 1;
+        self.accepts += accepted as u32;
+        if self.attempts < ADAPT_EVERY {
+            return;
+        }
+
+        if self.accepts * 5 > self.attempts {
+            self.sigma /= ADAPT_FACTOR;
+        } else if self.accepts * 5 < self.attempts {
+            self.sigma *= ADAPT_FACTOR;
+        }
+        self.sigma = self.sigma.clamp(MIN_SIGMA, MAX_SIGMA);
+        self.attempts = 0;
+        self.accepts = 0;
+    }
+}
+
+/// mutate picks a random shape and an operator with the weights of operators,
+/// and returns the index of the shape together with a copy mutated by the
+/// operator. With Gaussian mutations, sigma is the step size of StepSize. The
+/// translations are Gaussian steps sized by sigma with either mutation, and the
+/// rotations and scalings move the vertices about as far as a translation
+pub fn mutate<R: RngCore>(
+    rng: &mut R,
+    shapes: &[Primitive],
+    w: u32,
+    h: u32,
+    operators: &Operators,
+    mutation: Mutation,
+    sigma: f64,
+) -> (usize, Primitive, Operator) {
+    let operator = operators.pick(rng);
+    let index = rng.gen_range(0..shapes.len());
+
+    let mut new_shape = shapes[index].clone();
+
+    match operator {
+        Operator::Vertex => match mutation { // here we mutate a vertice, or whatever defines the geometry of the shape
+            Mutation::Uniform => new_shape.mutate(rng, w, h),
+            Mutation::Gaussian => new_shape.perturb(rng, sigma * w.max(h) as f64, w, h),
+        },
+        Operator::Move => {
+            // the center of the bounding box lands on a random point
+            let bounding_box = new_shape.bounding_box();
+            let to = random_point(rng, w, h);
+            let dx = to.x as i32 - (bounding_box.xmin + bounding_box.xmax) / 2;
+            let dy = to.y as i32 - (bounding_box.ymin + bounding_box.ymax) / 2;
+            new_shape.translate(dx, dy, w, h);
+        }
+        Operator::Channel => mutate_channels(rng, &mut new_shape.color_mut().0, ColorMutation::Channel, mutation, sigma),
+        Operator::Color => mutate_channels(rng, &mut new_shape.color_mut().0, ColorMutation::Replace, mutation, sigma),
+        Operator::Alpha => {
+            let alpha = &mut new_shape.color_mut().0[3];
+            *alpha = match mutation {
+                Mutation::Uniform => rng.gen(),
+                Mutation::Gaussian => nudge(rng, *alpha as u32, sigma * 255.0, 0, 255) as u8,
+            };
+        }
+        Operator::Translate => {
+            let step = sigma * w.max(h) as f64;
+            let (dx, dy) = (gaussian(rng, step).round() as i32, gaussian(rng, step).round() as i32);
+            new_shape.translate(dx, dy, w, h);
+        }
+        Operator::Scale => {
+            // the factor is log-normal so the shape grows as often as it shrinks, and
+            // growing by one standard deviation moves the vertices by the step
+            let step = sigma * w.max(h) as f64;
+            let factor = gaussian(rng, (step / radius(&new_shape)).ln_1p()).exp();
+            new_shape.scale(factor, w, h);
+        }
+        Operator::Rotate => {
+            // turning by one standard deviation moves the vertices by the step,
+            // up to half a turn for a shape smaller than the step
+            let step = sigma * w.max(h) as f64;
+            let angle = gaussian(rng, 2.0 * (step / (2.0 * radius(&new_shape))).min(1.0).asin());
+            new_shape.rotate(angle, w, h);
+        }
+    }
+
+    (index, new_shape, operator)
+}
+
+// radius returns half the diagonal of the bounding box of a shape, about how far
+// its vertices are from its center, to size the factors and angles of scale and
+// rotate so a small shape moves by as many pixels as a large one
+fn radius(shape: &Primitive) -> f64 {
+    let bounding_box = shape.bounding_box();
+    let (width, height) = ((bounding_box.xmax - bounding_box.xmin) as f64, (bounding_box.ymax - bounding_box.ymin) as f64);
+    (width.hypot(height) / 2.0).max(1.0)
+}
+
+/// mutate_channels mutates the channels of a color, all of them or a single one
+/// depending on the color mutation, the way mutate changes the color of a shape
+pub fn mutate_channels<R: RngCore + ?Sized>(
+    rng: &mut R,
+    channels: &mut [u8],
+    color_mutation: ColorMutation,
+    mutation: Mutation,
+    sigma: f64,
+) {
+    match (mutation, color_mutation) {
+        (Mutation::Uniform, ColorMutation::Replace) => channels.iter_mut().for_each(|channel| *channel = rng.gen()),
+        (Mutation::Uniform, ColorMutation::Channel) => {
+            let color_index = rng.gen_range(0..channels.len());
+            channels[color_index] = rng.gen();
+        }
+        (Mutation::Gaussian, ColorMutation::Replace) => {
+            for channel in channels.iter_mut() {
+                *channel = nudge(rng, *channel as u32, sigma * 255.0, 0, 255) as u8;
+            }
+        }
+        (Mutation::Gaussian, ColorMutation::Channel) => {
+            let color_index = rng.gen_range(0..channels.len());
+            channels[color_index] = nudge(rng, channels[color_index] as u32, sigma * 255.0, 0, 255) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::{Point, Triangle};
+    use image::Rgba;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    // triangle returns a shape with the given vertices
+    fn triangle(points: [(u32, u32); 3]) -> Primitive {
+        let points = points.map(|(x, y)| Point { x, y });
+        Primitive::Triangle(Triangle { points, color: Rgba([0, 0, 0, 128]) })
+    }
+
+    // vertices returns the vertices of a triangle
+    fn vertices(shape: &Primitive) -> [(f64, f64); 3] {
+        match shape {
+            Primitive::Triangle(triangle) => triangle.points.map(|point| (point.x as f64, point.y as f64)),
+            _ => unreachable!("only triangles are mutated here"),
+        }
+    }
+
+    // samples mutates a triangle many times with a single operator
+    fn samples(shape: &Primitive, operator: Operator, size: u32, sigma: f64) -> Vec<Primitive> {
+        let mut operators = Operators::default();
+        *operators.weight_mut(operator) = 1;
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let shapes = [shape.clone()];
+        (0..2000).map(|_| mutate(&mut rng, &shapes, size, size, &operators, Mutation::Gaussian, sigma).1).collect()
+    }
+
+    // displacement returns the root mean square distance the vertices of the samples moved by
+    fn displacement(shape: &Primitive, samples: &[Primitive]) -> f64 {
+        let before = vertices(shape);
+        let total: f64 = samples.iter()
+            .flat_map(|sample| vertices(sample).into_iter().zip(before))
+            .map(|(after, before)| (after.0 - before.0).powi(2) + (after.1 - before.1).powi(2))
+            .sum();
+        (total / (3 * samples.len()) as f64).sqrt()
+    }
+
+    // centroid returns the mean of the vertices of a triangle
+    fn centroid(shape: &Primitive) -> (f64, f64) {
+        let vertices = vertices(shape);
+        let (x, y) = vertices.iter().fold((0.0, 0.0), |(x, y), vertex| (x + vertex.0, y + vertex.1));
+        (x / 3.0, y / 3.0)
+    }
+
+    #[test]
+    fn pick_never_draws_zero_weights() {
+        let operators = Operators { vertex: 3, translate: 1, alpha: 2, ..Operators::default() };
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let mut counts = [0u32; Operator::ALL.len()];
+        for _ in 0..6000 {
+            counts[operators.pick(&mut rng) as usize] += 1;
+        }
+        for operator in Operator::ALL {
+            let (count, weight) = (counts[operator as usize], operators.weight(operator));
+            if weight == 0 {
+                assert_eq!(count, 0, "{} has no weight", operator);
+            } else {
+                // each operator is drawn about weight / total of the time
+                assert!(count.abs_diff(weight * 1000) < 150, "{} drawn {} times with a weight of {}", operator, count, weight);
+            }
+        }
+    }
+
+    #[test]
+    fn scale_and_rotate_keep_the_centroid() {
+        let shape = triangle([(90, 95), (110, 92), (100, 112)]);
+        for operator in [Operator::Scale, Operator::Rotate] {
+            for sample in samples(&shape, operator, 200, 0.02) {
+                let ((x, y), (cx, cy)) = (centroid(&sample), centroid(&shape));
+                // vertices are rounded to pixels, which moves the centroid by less than a pixel
+                assert!((x - cx).hypot(y - cy) < 1.0, "{} moved the centroid to {:?}", operator, (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn scale_and_rotate_stay_on_the_canvas() {
+        for points in [[(0, 1), (4, 0), (2, 5)], [(45, 49), (50, 44), (47, 50)], [(2, 3), (48, 25), (10, 47)]] {
+            let shape = triangle(points);
+            for operator in [Operator::Scale, Operator::Rotate] {
+                for sample in samples(&shape, operator, 50, 0.3) {
+                    let inside = vertices(&sample).iter().all(|&(x, y)| x <= 50.0 && y <= 50.0);
+                    assert!(inside, "{} moved {:?} off the canvas", operator, vertices(&sample));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scale_and_rotate_move_vertices_as_far_as_the_step() {
+        // the step of a translation along each axis
+        let step = 0.05 * 200.0;
+        for points in [[(90, 95), (110, 92), (100, 112)], [(60, 60), (140, 70), (90, 150)]] {
+            let shape = triangle(points);
+            for operator in [Operator::Scale, Operator::Rotate] {
+                let distance = displacement(&shape, &samples(&shape, operator, 200, 0.05));
+                assert!((0.6 * step..1.25 * step).contains(&distance), "{} moved {:?} by {}", operator, points, distance);
+            }
+        }
+    }
+
+    #[test]
+    fn scale_rarely_blows_up_or_collapses_a_small_shape() {
+        let shape = triangle([(100, 100), (103, 100), (101, 103)]);
+        let samples = samples(&shape, Operator::Scale, 200, 0.022);
+        let blown_up = samples.iter().filter(|sample| radius(sample) > 20.0).count();
+        let collapsed = samples.iter().filter(|sample| vertices(sample).iter().all(|&vertex| vertex == vertices(sample)[0])).count();
+        assert!(blown_up < samples.len() / 50, "{} of {} grew past 40 pixels", blown_up, samples.len());
+        assert!(collapsed < samples.len() / 50, "{} of {} collapsed", collapsed, samples.len());
+    }
+
+    // adapted returns the step size after a round of attempts with the given number accepted
+    fn adapted(sigma: f64, accepts: u32) -> f64 {
+        let mut step_size = StepSize::new(sigma);
+        for attempt in 0..ADAPT_EVERY {
+            step_size.update(attempt < accepts);
+        }
+        step_size.sigma()
+    }
+
+    #[test]
+    fn step_size_follows_the_one_fifth_rule() {
+        let fifth = ADAPT_EVERY / 5;
+        assert_eq!(adapted(0.1, fifth - 1), 0.1 * ADAPT_FACTOR);
+        assert_eq!(adapted(0.1, fifth + 1), 0.1 / ADAPT_FACTOR);
+        assert_eq!(adapted(0.1, fifth), 0.1);
+    }
+
+    #[test]
+    fn step_size_waits_for_a_full_round() {
+        let mut step_size = StepSize::new(0.1);
+        for _ in 1..ADAPT_EVERY {
+            step_size.update(true);
+        }
+        assert_eq!(step_size.sigma(), 0.1);
+        step_size.update(true);
+        assert_eq!(step_size.sigma(), 0.1 / ADAPT_FACTOR);
+
+        // the next round counts from zero
+        for _ in 0..ADAPT_EVERY {
+            step_size.update(false);
+        }
+        assert_eq!(step_size.sigma(), 0.1);
+    }
+
+    #[test]
+    fn step_size_stays_within_its_bounds() {
+        assert_eq!(adapted(MAX_SIGMA, ADAPT_EVERY), MAX_SIGMA);
+        assert_eq!(adapted(MIN_SIGMA, 0), MIN_SIGMA);
+        assert_eq!(StepSize::new(2.0).sigma(), MAX_SIGMA);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::evolver::Step;
+use crate::mutate::{MutationKind, Operator};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// LogFormat is the format of the records of a progress log
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// comma separated values, with a header line
+    Csv,
+    /// one JSON object per line
+    Jsonl,
+}
+
+impl LogFormat {
+    /// from_path returns the format matching the extension of a file: csv, or jsonl or ndjson.
+    /// A .json file isn't one, since JSON Lines aren't a JSON document
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<LogFormat> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(LogFormat::Csv),
+            "jsonl" | "ndjson" => Some(LogFormat::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+/// Record is what a progress log saves about an epoch
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Record {
+    /// epoch number, starting at 0
+    pub epoch: u64,
+    /// distance between the closest image found so far and the target
+    pub best_distance: f64,
+    /// distance of the best candidate of the epoch
+    pub distance: f64,
+    /// whether the mutation was kept
+    pub accepted: bool,
+    /// what the mutation changed
+    pub kind: MutationKind,
+    /// operator that mutated the shape, none when the mutation didn't change a shape
+    pub operator: Option<Operator>,
+    /// index of the mutated shape
+    pub index: usize,
+    /// seconds since the run started
+    pub elapsed: f64,
+}
+
+impl Record {
+    /// new creates the record of a step
+    pub fn new(step: &Step, best_distance: f64, elapsed: Duration) -> Record {
+        Record {
+            epoch: step.epoch,
+            best_distance,
+            distance: step.distance,
+            accepted: step.accepted,
+            kind: step.kind,
+            operator: step.operator,
+            index: step.index,
+            elapsed: elapsed.as_secs_f64(),
+        }
+    }
+}
+
+/// ProgressLog writes a record per epoch, so runs can be plotted and compared
+pub struct ProgressLog<W: Write> {
+    writer: W,
+    format: LogFormat,
+}
+
+impl ProgressLog<BufWriter<File>> {
+    /// create opens a log file, in the format of its extension. When appending,
+    /// the records follow the ones already in the file, to continue a resumed run
+    pub fn create<P: AsRef<Path>>(path: P, append: bool) -> io::Result<ProgressLog<BufWriter<File>>> {
+        let path = path.as_ref();
+        let format = LogFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the log must end with .csv, .jsonl or .ndjson")
+        })?;
+
+        let file = OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+        let mut log = ProgressLog { writer: BufWriter::new(file), format };
+        if empty {
+            log.header()?;
+        }
+        Ok(log)
+    }
+}
+
+impl<W: Write> ProgressLog<W> {
+    /// new writes a log in the given format, with the CSV header
+    pub fn new(writer: W, format: LogFormat) -> io::Result<ProgressLog<W>> {
+        let mut log = ProgressLog { writer, format };
+        log.header()?;
+        Ok(log)
+    }
+
+    /// write adds a record to the log
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        match self.format {
+            LogFormat::Csv => writeln!(
+                self.writer,
+                "{},{},{},{},{},{},{},{:.6}",
+                record.epoch,
+                record.best_distance,
+                record.distance,
+                record.accepted,
+                record.kind,
+                record.operator.map(|operator| operator.to_string()).unwrap_or_default(),
+                record.index,
+                record.elapsed,
+            ),
+            LogFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                writeln!(self.writer)
+            }
+        }
+    }
+
+    /// flush writes the buffered records to the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    // header writes the names of the columns of a CSV log
+    fn header(&mut self) -> io::Result<()> {
+        match self.format {
+            LogFormat::Csv => writeln!(self.writer, "epoch,best_distance,distance,accepted,kind,operator,index,elapsed"),
+            LogFormat::Jsonl => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // record returns the record of an accepted mutation made by the operator
+    fn record(operator: Option<Operator>) -> Record {
+        Record {
+            epoch: 42, best_distance: 12.5, distance: 13.25, accepted: true,
+            kind: MutationKind::Geometry, operator, index: 3, elapsed: 1.5,
+        }
+    }
+
+    // written returns what a log in the format holds after the records
+    fn written(format: LogFormat, records: &[Record]) -> String {
+        let mut log = ProgressLog::new(Vec::new(), format).unwrap();
+        for record in records {
+            log.write(record).unwrap();
+        }
+        String::from_utf8(log.writer).unwrap()
+    }
+
+    #[test]
+    fn csv_has_a_header_and_a_row_per_record() {
+        let csv = written(LogFormat::Csv, &[record(Some(Operator::Vertex)), Record { kind: MutationKind::Background, ..record(None) }]);
+        assert_eq!(csv, "epoch,best_distance,distance,accepted,kind,operator,index,elapsed\n\
+            42,12.5,13.25,true,geometry,vertex,3,1.500000\n\
+            42,12.5,13.25,true,background,,3,1.500000\n");
+    }
+
+    #[test]
+    fn jsonl_has_an_object_per_record() {
+        let jsonl = written(LogFormat::Jsonl, &[record(Some(Operator::Vertex)), record(None)]);
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines, [
+            r#"{"epoch":42,"best_distance":12.5,"distance":13.25,"accepted":true,"kind":"geometry","operator":"vertex","index":3,"elapsed":1.5}"#,
+            r#"{"epoch":42,"best_distance":12.5,"distance":13.25,"accepted":true,"kind":"geometry","operator":null,"index":3,"elapsed":1.5}"#,
+        ]);
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert_eq!(LogFormat::from_path("run.csv"), Some(LogFormat::Csv));
+        assert_eq!(LogFormat::from_path("run.CSV"), Some(LogFormat::Csv));
+        assert_eq!(LogFormat::from_path("logs/run.jsonl"), Some(LogFormat::Jsonl));
+        assert_eq!(LogFormat::from_path("run.ndjson"), Some(LogFormat::Jsonl));
+        for path in ["run.json", "run.txt", "run", "csv"] {
+            assert_eq!(LogFormat::from_path(path), None, "{}", path);
+        }
+        let err = ProgressLog::create("run.json", false).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
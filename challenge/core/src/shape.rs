@@ -0,0 +1,339 @@
+mod circle;
+mod ellipse;
+mod polygon;
+mod rectangle;
+mod triangle;
+
+pub use circle::Circle;
+pub use ellipse::Ellipse;
+pub use polygon::{convex_hull, Polygon};
+pub use rectangle::{Rectangle, RotatedRectangle};
+pub use triangle::Triangle;
+
+use image::Rgba;
+use rand::{Rng, RngCore};
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::f32::consts::PI;
+
+/// Shape is anything the evolver can stack in the image
+pub trait Shape {
+    /// rasterise calls f for every pixel of a w*h image that lies inside the shape
+    fn rasterise(&self, w: u32, h: u32, f: &mut dyn FnMut(u32, u32));
+
+    /// bounding_box returns the smallest rectangle holding the shape, which may exceed the image
+    fn bounding_box(&self) -> BoundingBox;
+
+    /// mutate changes the geometry of the shape at random, keeping it in a w*h image
+    fn mutate(&mut self, rng: &mut dyn RngCore, w: u32, h: u32);
+
+    /// perturb nudges the geometry of the shape by a Gaussian step with a
+    /// standard deviation of sigma pixels, keeping it in a w*h image
+    fn perturb(&mut self, rng: &mut dyn RngCore, sigma: f64, w: u32, h: u32);
+
+    /// rescale stretches the geometry of the shape by sx horizontally and sy
+    /// vertically, for an image resized to w*h
+    fn rescale(&mut self, sx: f64, sy: f64, w: u32, h: u32);
+
+    /// translate moves the shape by dx, dy pixels, keeping it in a w*h image
+    fn translate(&mut self, dx: i32, dy: i32, w: u32, h: u32);
+
+    /// scale stretches the shape by factor around its center, keeping it in a w*h image
+    fn scale(&mut self, factor: f64, w: u32, h: u32);
+
+    /// rotate turns the shape clockwise on screen by angle radians around its
+    /// center, keeping it in a w*h image. Axis-aligned shapes don't turn
+    fn rotate(&mut self, angle: f64, w: u32, h: u32);
+
+    /// color returns the RGBA color of the shape
+    fn color(&self) -> Rgba<u8>;
+
+    /// color_mut returns the RGBA color of the shape, to be mutated
+    fn color_mut(&mut self) -> &mut Rgba<u8>;
+
+    /// svg returns the SVG element drawing the shape, with the given attributes appended
+    fn svg(&self, attributes: &str) -> String;
+}
+
+/// Point is used by the shapes and represents a vertice
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Point {
+    pub x: u32,
+    pub y: u32
+}
+
+/// BoundingBox is a rectangle of pixels, from (xmin, ymin) included to (xmax, ymax) excluded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub xmin: i32,
+    pub ymin: i32,
+    pub xmax: i32,
+    pub ymax: i32,
+}
+
+impl BoundingBox {
+    // around returns the bounding box of a set of points
+    fn around(points: &[Point]) -> BoundingBox {
+        BoundingBox {
+            xmin: points.iter().map(|p| p.x).min().unwrap_or(0) as i32,
+            ymin: points.iter().map(|p| p.y).min().unwrap_or(0) as i32,
+            xmax: points.iter().map(|p| p.x).max().unwrap_or(0) as i32 + 1,
+            ymax: points.iter().map(|p| p.y).max().unwrap_or(0) as i32 + 1,
+        }
+    }
+}
+
+/// ShapeKind names the kinds of shape a run can use
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum ShapeKind {
+    Triangle,
+    Polygon,
+    Circle,
+    Ellipse,
+    Rectangle,
+    RotatedRectangle,
+}
+
+/// Primitive is a shape of any kind, stored in stacking order by the evolver
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Primitive {
+    Triangle(Triangle),
+    Polygon(Polygon),
+    Circle(Circle),
+    Ellipse(Ellipse),
+    Rectangle(Rectangle),
+    RotatedRectangle(RotatedRectangle),
+}
+
+impl Primitive {
+    fn shape(&self) -> &dyn Shape {
+        match self {
+            Primitive::Triangle(shape) => shape,
+            Primitive::Polygon(shape) => shape,
+            Primitive::Circle(shape) => shape,
+            Primitive::Ellipse(shape) => shape,
+            Primitive::Rectangle(shape) => shape,
+            Primitive::RotatedRectangle(shape) => shape,
+        }
+    }
+
+    fn shape_mut(&mut self) -> &mut dyn Shape {
+        match self {
+            Primitive::Triangle(shape) => shape,
+            Primitive::Polygon(shape) => shape,
+            Primitive::Circle(shape) => shape,
+            Primitive::Ellipse(shape) => shape,
+            Primitive::Rectangle(shape) => shape,
+            Primitive::RotatedRectangle(shape) => shape,
+        }
+    }
+}
+
+impl Shape for Primitive {
+    fn rasterise(&self, w: u32, h: u32, f: &mut dyn FnMut(u32, u32)) {
+        self.shape().rasterise(w, h, f)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.shape().bounding_box()
+    }
+
+    fn mutate(&mut self, rng: &mut dyn RngCore, w: u32, h: u32) {
+        self.shape_mut().mutate(rng, w, h)
+    }
+
+    fn perturb(&mut self, rng: &mut dyn RngCore, sigma: f64, w: u32, h: u32) {
+        self.shape_mut().perturb(rng, sigma, w, h)
+    }
+
+    fn rescale(&mut self, sx: f64, sy: f64, w: u32, h: u32) {
+        self.shape_mut().rescale(sx, sy, w, h)
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32, w: u32, h: u32) {
+        self.shape_mut().translate(dx, dy, w, h)
+    }
+
+    fn scale(&mut self, factor: f64, w: u32, h: u32) {
+        self.shape_mut().scale(factor, w, h)
+    }
+
+    fn rotate(&mut self, angle: f64, w: u32, h: u32) {
+        self.shape_mut().rotate(angle, w, h)
+    }
+
+    fn color(&self) -> Rgba<u8> {
+        self.shape().color()
+    }
+
+    fn color_mut(&mut self) -> &mut Rgba<u8> {
+        self.shape_mut().color_mut()
+    }
+
+    fn svg(&self, attributes: &str) -> String {
+        self.shape().svg(attributes)
+    }
+}
+
+/// random_shape creates a random shape of the given kind, polygons have the given
+/// number of vertices and are drawn as the convex hull of their vertices when convex is set
+pub fn random_shape<R: Rng + ?Sized>(rng: &mut R, w: u32, h: u32, kind: ShapeKind, vertices: usize, convex: bool) -> Primitive {
+    match kind {
+        ShapeKind::Triangle => Primitive::Triangle(random_triangle(rng, w, h)),
+        ShapeKind::Polygon => Primitive::Polygon(random_polygon(rng, w, h, vertices, convex)),
+        ShapeKind::Circle => Primitive::Circle(Circle {
+            center: random_point(rng, w, h),
+            radius: random_radius(rng, w, h),
+            color: random_color_rgba(rng),
+        }),
+        ShapeKind::Ellipse => Primitive::Ellipse(Ellipse {
+            center: random_point(rng, w, h),
+            rx: random_radius(rng, w, h),
+            ry: random_radius(rng, w, h),
+            color: random_color_rgba(rng),
+        }),
+        ShapeKind::Rectangle => Primitive::Rectangle(Rectangle {
+            points: [random_point(rng, w, h), random_point(rng, w, h)],
+            color: random_color_rgba(rng),
+        }),
+        ShapeKind::RotatedRectangle => Primitive::RotatedRectangle(RotatedRectangle {
+            center: random_point(rng, w, h),
+            width: 2 * random_radius(rng, w, h),
+            height: 2 * random_radius(rng, w, h),
+            angle: rng.gen_range(0.0..PI),
+            color: random_color_rgba(rng),
+        }),
+    }
+}
+
+/// random_polygon creates and returns a polygon with random vertices and random color
+pub fn random_polygon<R: Rng + ?Sized>(rng: &mut R, w: u32, h: u32, vertices: usize, convex: bool) -> Polygon {
+    Polygon {
+        points: (0..vertices).map(|_| random_point(rng, w, h)).collect(),
+        color: random_color_rgba(rng),
+        convex,
+    }
+}
+
+/// random_triangle creates and returns a random triangle
+/// with random vertices (points) and random color
+pub fn random_triangle<R: Rng + ?Sized>(rng: &mut R, w: u32, h: u32) -> Triangle {
+    Triangle {
+        points: [
+            random_point(rng, w, h),
+            random_point(rng, w, h),
+            random_point(rng, w, h),
+        ],
+        color: random_color_rgba(rng)
+    }
+}
+
+/// random_color_rgba creates and return a random rgba color
+pub fn random_color_rgba<R: Rng + ?Sized>(rng: &mut R) -> Rgba<u8> {
+    let color= [
+        rng.gen(),
+        rng.gen(),
+        rng.gen(),
+        rng.gen()
+    ];
+    Rgba(color)
+}
+
+/// random_point creates and returns a random point, the bounds are inclusive
+/// so a triangle can reach the last row and column of the image
+pub fn random_point<R: Rng + ?Sized>(rng: &mut R, w: u32, h: u32) -> Point {
+    Point{
+        x: rng.gen_range(0..=w),
+        y: rng.gen_range(0..=h)
+    }
+}
+
+// random_radius returns a radius from 1 to half the largest side of the image
+fn random_radius<R: Rng + ?Sized>(rng: &mut R, w: u32, h: u32) -> u32 {
+    rng.gen_range(1..=max_radius(w, h))
+}
+
+// max_radius returns the largest radius random_radius can pick
+fn max_radius(w: u32, h: u32) -> u32 {
+    (w.max(h) / 2).max(1)
+}
+
+/// gaussian returns a random value of a normal distribution centered on 0
+/// with a standard deviation of sigma
+pub fn gaussian<R: Rng + ?Sized>(rng: &mut R, sigma: f64) -> f64 {
+    rng.sample::<f64, _>(StandardNormal) * sigma
+}
+
+/// nudge adds a Gaussian step of standard deviation sigma to value and keeps
+/// it between min and max. The value moves by at least 1 so small steps are never lost
+pub fn nudge<R: Rng + ?Sized>(rng: &mut R, value: u32, sigma: f64, min: u32, max: u32) -> u32 {
+    let step = gaussian(rng, sigma).round() as i64;
+    let step = if step != 0 { step } else if rng.gen() { 1 } else { -1 };
+    (value as i64 + step).clamp(min as i64, max as i64) as u32
+}
+
+// nudge_point moves a point by a Gaussian step, inside the bounds of random_point
+fn nudge_point<R: Rng + ?Sized>(rng: &mut R, point: Point, sigma: f64, w: u32, h: u32) -> Point {
+    Point {
+        x: nudge(rng, point.x, sigma, 0, w),
+        y: nudge(rng, point.y, sigma, 0, h),
+    }
+}
+
+// rescale_point stretches a point by sx and sy, inside the bounds of random_point
+fn rescale_point(point: Point, sx: f64, sy: f64, w: u32, h: u32) -> Point {
+    Point {
+        x: ((point.x as f64 * sx).round() as u32).min(w),
+        y: ((point.y as f64 * sy).round() as u32).min(h),
+    }
+}
+
+// translate_point moves a point by dx, dy, inside the bounds of random_point
+fn translate_point(point: Point, dx: i32, dy: i32, w: u32, h: u32) -> Point {
+    Point {
+        x: (point.x as i64 + dx as i64).clamp(0, w as i64) as u32,
+        y: (point.y as i64 + dy as i64).clamp(0, h as i64) as u32,
+    }
+}
+
+// centroid returns the mean of the points
+fn centroid(points: &[Point]) -> (f64, f64) {
+    let count = points.len().max(1) as f64;
+    let (x, y) = points.iter().fold((0.0, 0.0), |(x, y), point| (x + point.x as f64, y + point.y as f64));
+    (x / count, y / count)
+}
+
+// scale_point moves a point factor times farther from the center, inside the bounds of random_point
+fn scale_point(point: Point, (cx, cy): (f64, f64), factor: f64, w: u32, h: u32) -> Point {
+    Point {
+        x: (cx + (point.x as f64 - cx) * factor).round().clamp(0.0, w as f64) as u32,
+        y: (cy + (point.y as f64 - cy) * factor).round().clamp(0.0, h as f64) as u32,
+    }
+}
+
+// rotate_point turns a point by angle radians around the center, inside the bounds of random_point
+fn rotate_point(point: Point, (cx, cy): (f64, f64), angle: f64, w: u32, h: u32) -> Point {
+    let (sin, cos) = angle.sin_cos();
+    let (dx, dy) = (point.x as f64 - cx, point.y as f64 - cy);
+    Point {
+        x: (cx + dx * cos - dy * sin).round().clamp(0.0, w as f64) as u32,
+        y: (cy + dx * sin + dy * cos).round().clamp(0.0, h as f64) as u32,
+    }
+}
+
+// rescale_length stretches a length by a factor, keeping it from min to max
+fn rescale_length(length: u32, factor: f64, min: u32, max: u32) -> u32 {
+    ((length as f64 * factor).round() as u32).clamp(min, max)
+}
+
+// colors are saved as an [r, g, b, a] array, since Rgba doesn't implement serde
+pub(crate) fn serialize_color<S: Serializer>(color: &Rgba<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+    color.0.serialize(serializer)
+}
+
+pub(crate) fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgba<u8>, D::Error> {
+    Ok(Rgba(<[u8; 4]>::deserialize(deserializer)?))
+}
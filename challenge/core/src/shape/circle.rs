@@ -0,0 +1,117 @@
+use super::{deserialize_color, max_radius, nudge, nudge_point, random_point, random_radius, rescale_length, rescale_point, serialize_color, translate_point, BoundingBox, Point, Shape};
+use image::Rgba;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// Circle is a disc around its center
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: u32,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub color: Rgba<u8>,
+}
+
+impl Shape for Circle {
+    fn rasterise(&self, w: u32, h: u32, f: &mut dyn FnMut(u32, u32)) {
+        let (cx, cy, r) = (self.center.x as i64, self.center.y as i64, self.radius as i64);
+
+        // a pixel is inside when (x - cx)² + (y - cy)² <= r²
+        for y in (cy - r).max(0) ..= (cy + r).min(h as i64 - 1) {
+            let half = ((r * r - (y - cy) * (y - cy)) as f64).sqrt() as i64;
+            for x in (cx - half).max(0) ..= (cx + half).min(w as i64 - 1) {
+                f(x as u32, y as u32);
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let (cx, cy, r) = (self.center.x as i32, self.center.y as i32, self.radius as i32);
+        BoundingBox { xmin: cx - r, ymin: cy - r, xmax: cx + r + 1, ymax: cy + r + 1 }
+    }
+
+    // mutate moves the center or changes the radius
+    fn mutate(&mut self, rng: &mut dyn RngCore, w: u32, h: u32) {
+        if rng.gen_range(0..2) == 0 {
+            self.center = random_point(rng, w, h);
+        } else {
+            self.radius = random_radius(rng, w, h);
+        }
+    }
+
+    // perturb moves the center or changes the radius by a Gaussian step
+    fn perturb(&mut self, rng: &mut dyn RngCore, sigma: f64, w: u32, h: u32) {
+        if rng.gen_range(0..2) == 0 {
+            self.center = nudge_point(rng, self.center, sigma, w, h);
+        } else {
+            self.radius = nudge(rng, self.radius, sigma, 1, max_radius(w, h));
+        }
+    }
+
+    // rescale stretches the radius by the mean of the 2 factors, which are the
+    // same when the image keeps its proportions
+    fn rescale(&mut self, sx: f64, sy: f64, w: u32, h: u32) {
+        self.center = rescale_point(self.center, sx, sy, w, h);
+        self.radius = rescale_length(self.radius, (sx + sy) / 2.0, 1, max_radius(w, h));
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32, w: u32, h: u32) {
+        self.center = translate_point(self.center, dx, dy, w, h);
+    }
+
+    fn scale(&mut self, factor: f64, w: u32, h: u32) {
+        self.radius = rescale_length(self.radius, factor, 1, max_radius(w, h));
+    }
+
+    // rotate leaves the circle as it is, it looks the same at any angle
+    fn rotate(&mut self, _angle: f64, _w: u32, _h: u32) {}
+
+    fn color(&self) -> Rgba<u8> {
+        self.color
+    }
+
+    fn color_mut(&mut self) -> &mut Rgba<u8> {
+        &mut self.color
+    }
+
+    fn svg(&self, attributes: &str) -> String {
+        format!(r#"<circle cx="{}" cy="{}" r="{}" {}/>"#, self.center.x, self.center.y, self.radius, attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // pixels returns the pixels a shape covers in a w*h image, in order
+    fn pixels(shape: &dyn Shape, w: u32, h: u32) -> Vec<(u32, u32)> {
+        let mut pixels = vec![];
+        shape.rasterise(w, h, &mut |x, y| pixels.push((x, y)));
+        pixels.sort();
+        pixels
+    }
+
+    // inside returns the pixels of a w*h image within the radius of the center
+    fn inside(cx: i64, cy: i64, r: i64, w: u32, h: u32) -> Vec<(u32, u32)> {
+        (0..w).flat_map(|x| (0..h).map(move |y| (x, y)))
+            .filter(|&(x, y)| (x as i64 - cx).pow(2) + (y as i64 - cy).pow(2) <= r * r)
+            .collect()
+    }
+
+    fn circle(x: u32, y: u32, radius: u32) -> Circle {
+        Circle { center: Point { x, y }, radius, color: Rgba([0, 0, 0, 255]) }
+    }
+
+    #[test]
+    fn covers_the_pixels_within_the_radius() {
+        assert_eq!(pixels(&circle(5, 5, 2), 20, 20).len(), 13);
+        for radius in 0..8 {
+            assert_eq!(pixels(&circle(10, 9, radius), 20, 20), inside(10, 9, radius as i64, 20, 20), "radius {radius}");
+        }
+    }
+
+    #[test]
+    fn is_clipped_to_the_image() {
+        assert_eq!(pixels(&circle(1, 18, 5), 20, 20), inside(1, 18, 5, 20, 20));
+    }
+}
@@ -0,0 +1,129 @@
+use super::{deserialize_color, max_radius, nudge, nudge_point, random_point, random_radius, rescale_length, rescale_point, serialize_color, translate_point, BoundingBox, Point, Shape};
+use image::Rgba;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// Ellipse is an axis-aligned ellipse around its center, with a horizontal
+/// radius rx and a vertical radius ry
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ellipse {
+    pub center: Point,
+    pub rx: u32,
+    pub ry: u32,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub color: Rgba<u8>,
+}
+
+impl Shape for Ellipse {
+    fn rasterise(&self, w: u32, h: u32, f: &mut dyn FnMut(u32, u32)) {
+        let (cx, cy) = (self.center.x as i64, self.center.y as i64);
+        let (rx, ry) = (self.rx.max(1) as i64, self.ry.max(1) as i64);
+
+        // a pixel is inside when ((x - cx) / rx)² + ((y - cy) / ry)² <= 1, computed
+        // in integers so the pixels right on the edge aren't lost to rounding
+        for y in (cy - ry).max(0) ..= (cy + ry).min(h as i64 - 1) {
+            let dy = y - cy;
+            let half = (rx * rx * (ry * ry - dy * dy) / (ry * ry)).isqrt();
+            for x in (cx - half).max(0) ..= (cx + half).min(w as i64 - 1) {
+                f(x as u32, y as u32);
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let (cx, cy) = (self.center.x as i32, self.center.y as i32);
+        let (rx, ry) = (self.rx as i32, self.ry as i32);
+        BoundingBox { xmin: cx - rx, ymin: cy - ry, xmax: cx + rx + 1, ymax: cy + ry + 1 }
+    }
+
+    // mutate moves the center or changes one of the radii
+    fn mutate(&mut self, rng: &mut dyn RngCore, w: u32, h: u32) {
+        match rng.gen_range(0..3) {
+            0 => self.center = random_point(rng, w, h),
+            1 => self.rx = random_radius(rng, w, h),
+            _ => self.ry = random_radius(rng, w, h),
+        }
+    }
+
+    // perturb moves the center or changes a radius by a Gaussian step
+    fn perturb(&mut self, rng: &mut dyn RngCore, sigma: f64, w: u32, h: u32) {
+        match rng.gen_range(0..3) {
+            0 => self.center = nudge_point(rng, self.center, sigma, w, h),
+            1 => self.rx = nudge(rng, self.rx, sigma, 1, max_radius(w, h)),
+            _ => self.ry = nudge(rng, self.ry, sigma, 1, max_radius(w, h)),
+        }
+    }
+
+    fn rescale(&mut self, sx: f64, sy: f64, w: u32, h: u32) {
+        self.center = rescale_point(self.center, sx, sy, w, h);
+        self.rx = rescale_length(self.rx, sx, 1, max_radius(w, h));
+        self.ry = rescale_length(self.ry, sy, 1, max_radius(w, h));
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32, w: u32, h: u32) {
+        self.center = translate_point(self.center, dx, dy, w, h);
+    }
+
+    fn scale(&mut self, factor: f64, w: u32, h: u32) {
+        self.rx = rescale_length(self.rx, factor, 1, max_radius(w, h));
+        self.ry = rescale_length(self.ry, factor, 1, max_radius(w, h));
+    }
+
+    // rotate leaves the ellipse as it is, its axes stay aligned with the image
+    fn rotate(&mut self, _angle: f64, _w: u32, _h: u32) {}
+
+    fn color(&self) -> Rgba<u8> {
+        self.color
+    }
+
+    fn color_mut(&mut self) -> &mut Rgba<u8> {
+        &mut self.color
+    }
+
+    fn svg(&self, attributes: &str) -> String {
+        format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" {}/>"#, self.center.x, self.center.y, self.rx, self.ry, attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::Circle;
+
+    // pixels returns the pixels a shape covers in a w*h image, in order
+    fn pixels(shape: &dyn Shape, w: u32, h: u32) -> Vec<(u32, u32)> {
+        let mut pixels = vec![];
+        shape.rasterise(w, h, &mut |x, y| pixels.push((x, y)));
+        pixels.sort();
+        pixels
+    }
+
+    fn ellipse(x: u32, y: u32, rx: u32, ry: u32) -> Ellipse {
+        Ellipse { center: Point { x, y }, rx, ry, color: Rgba([0, 0, 0, 255]) }
+    }
+
+    #[test]
+    fn covers_the_pixels_within_the_radii() {
+        let covered = pixels(&ellipse(10, 10, 6, 3), 20, 20);
+        let inside: Vec<(u32, u32)> = (0..20u32).flat_map(|x| (0..20u32).map(move |y| (x, y)))
+            .filter(|&(x, y)| 9 * (x as i64 - 10).pow(2) + 36 * (y as i64 - 10).pow(2) <= 36 * 9)
+            .collect();
+        assert_eq!(covered, inside);
+        // the widest row spans both radii and the tallest column both others
+        assert_eq!(covered.iter().filter(|p| p.1 == 10).count(), 13);
+        assert_eq!(covered.iter().filter(|p| p.0 == 10).count(), 7);
+    }
+
+    #[test]
+    fn equal_radii_cover_a_circle() {
+        let circle = Circle { center: Point { x: 8, y: 7 }, radius: 5, color: Rgba([0, 0, 0, 255]) };
+        assert_eq!(pixels(&ellipse(8, 7, 5, 5), 20, 20), pixels(&circle, 20, 20));
+    }
+
+    #[test]
+    fn is_clipped_to_the_image() {
+        let covered = pixels(&ellipse(18, 1, 6, 4), 20, 20);
+        assert!(covered.iter().all(|&(x, y)| x < 20 && y < 20));
+        assert_eq!(covered, pixels(&ellipse(18, 1, 6, 4), 40, 40).into_iter().filter(|&(x, y)| x < 20 && y < 20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,217 @@
+use super::{centroid, deserialize_color, nudge_point, random_point, rescale_point, rotate_point, scale_point, serialize_color, translate_point, BoundingBox, Point, Shape};
+use image::Rgba;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::cmp::min;
+
+/// Polygon is a shape with any number of vertices. A convex polygon is drawn
+/// as the convex hull of its vertices, otherwise the vertices are joined in
+/// order and the inside is found with the even-odd rule, so edges may cross
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Polygon {
+    pub points: Vec<Point>,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub color: Rgba<u8>,
+    pub convex: bool,
+}
+
+impl Polygon {
+    /// outline returns the vertices of the drawn outline, in order
+    pub fn outline(&self) -> Vec<Point> {
+        if self.convex { convex_hull(&self.points) } else { self.points.clone() }
+    }
+}
+
+impl Shape for Polygon {
+    fn rasterise(&self, w: u32, h: u32, f: &mut dyn FnMut(u32, u32)) {
+        let outline: Vec<(f64, f64)> = self.outline().iter().map(|p| (p.x as f64, p.y as f64)).collect();
+        fill_polygon(&outline, w, h, f);
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::around(&self.points)
+    }
+
+    // mutate moves a vertice to a random point
+    fn mutate(&mut self, rng: &mut dyn RngCore, w: u32, h: u32) {
+        let vertice_index = rng.gen_range(0..self.points.len());
+        self.points[vertice_index] = random_point(rng, w, h);
+    }
+
+    // perturb moves a vertice by a Gaussian step
+    fn perturb(&mut self, rng: &mut dyn RngCore, sigma: f64, w: u32, h: u32) {
+        let vertice_index = rng.gen_range(0..self.points.len());
+        self.points[vertice_index] = nudge_point(rng, self.points[vertice_index], sigma, w, h);
+    }
+
+    fn rescale(&mut self, sx: f64, sy: f64, w: u32, h: u32) {
+        self.points.iter_mut().for_each(|point| *point = rescale_point(*point, sx, sy, w, h));
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32, w: u32, h: u32) {
+        self.points.iter_mut().for_each(|point| *point = translate_point(*point, dx, dy, w, h));
+    }
+
+    // scale moves the vertices away from their centroid, or closer to it
+    fn scale(&mut self, factor: f64, w: u32, h: u32) {
+        let center = centroid(&self.points);
+        self.points.iter_mut().for_each(|point| *point = scale_point(*point, center, factor, w, h));
+    }
+
+    // rotate turns the vertices around their centroid
+    fn rotate(&mut self, angle: f64, w: u32, h: u32) {
+        let center = centroid(&self.points);
+        self.points.iter_mut().for_each(|point| *point = rotate_point(*point, center, angle, w, h));
+    }
+
+    fn color(&self) -> Rgba<u8> {
+        self.color
+    }
+
+    fn color_mut(&mut self) -> &mut Rgba<u8> {
+        &mut self.color
+    }
+
+    fn svg(&self, attributes: &str) -> String {
+        let points: Vec<String> = self.outline().iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+        format!(r#"<polygon points="{}" {}/>"#, points.join(" "), attributes)
+    }
+}
+
+/// convex_hull returns the vertices of the convex hull of the points in
+/// order around the hull, using Andrew's monotone chain
+pub fn convex_hull(points: &[Point]) -> Vec<Point> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|p| (p.x, p.y));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    // cross is positive when o, a and b make a left turn
+    let cross = |o: &Point, a: &Point, b: &Point| {
+        (a.x as i64 - o.x as i64) * (b.y as i64 - o.y as i64) - (a.y as i64 - o.y as i64) * (b.x as i64 - o.x as i64)
+    };
+    let chain = |points: &mut dyn Iterator<Item = &Point>| {
+        let mut chain: Vec<Point> = vec![];
+        for p in points {
+            while chain.len() >= 2 && cross(&chain[chain.len() - 2], &chain[chain.len() - 1], p) <= 0 {
+                chain.pop();
+            }
+            chain.push(*p);
+        }
+        // the last point of each chain is the first point of the other one
+        chain.pop();
+        chain
+    };
+
+    let mut hull = chain(&mut sorted.iter());
+    hull.extend(chain(&mut sorted.iter().rev()));
+    hull
+}
+
+// fill_polygon calls f for every pixel of a w*h image that lies inside the
+// polygon, with the even-odd rule. Each row is sampled at its integer y and
+// filled between pairs of edge crossings, the pixel x being inside when
+// x_left <= x < x_right, like the edges of a triangle
+pub(super) fn fill_polygon(points: &[(f64, f64)], w: u32, h: u32, f: &mut dyn FnMut(u32, u32)) {
+    if points.len() < 3 {
+        return;
+    }
+
+    let ymin = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min).ceil().max(0.0) as u32;
+    let ymax = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max).ceil().max(0.0) as u32;
+
+    let mut crossings: Vec<f64> = Vec::with_capacity(points.len());
+    for y in ymin .. min(ymax, h) {
+        let yf = y as f64;
+
+        // x of every edge crossing the row, an edge holds its lower end but not its upper one
+        crossings.clear();
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            if (y1 <= yf) != (y2 <= yf) {
+                crossings.push(x1 + (yf - y1) * (x2 - x1) / (y2 - y1));
+            }
+        }
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        for pair in crossings.chunks_exact(2) {
+            let xstart = pair[0].ceil().max(0.0) as u32;
+            let xend = min(pair[1].ceil().max(0.0) as u32, w);
+            for x in xstart .. xend {
+                f(x, y);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // polygon returns a black polygon with the given vertices
+    fn polygon(points: &[(u32, u32)], convex: bool) -> Polygon {
+        let points = points.iter().map(|&(x, y)| Point { x, y }).collect();
+        Polygon { points, color: Rgba([0, 0, 0, 255]), convex }
+    }
+
+    // pixels returns the pixels a shape covers in a w*h image
+    fn pixels(shape: &dyn Shape, w: u32, h: u32) -> Vec<(u32, u32)> {
+        let mut pixels = vec![];
+        shape.rasterise(w, h, &mut |x, y| pixels.push((x, y)));
+        pixels
+    }
+
+    // star returns the vertices of a pentagram centered on (20, 20), joined
+    // every other tip so its edges cross around the center
+    fn star() -> Vec<(u32, u32)> {
+        (0..5)
+            .map(|i| {
+                let angle = (i * 2 % 5) as f64 * 2.0 * std::f64::consts::PI / 5.0 - std::f64::consts::FRAC_PI_2;
+                ((20.0 + 18.0 * angle.cos()).round() as u32, (20.0 + 18.0 * angle.sin()).round() as u32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn convex_hull_drops_interior_and_aligned_points() {
+        let points: Vec<Point> = [(0, 0), (2, 2), (4, 0), (1, 3), (4, 4), (2, 0), (0, 4)]
+            .map(|(x, y)| Point { x, y })
+            .to_vec();
+        let hull = convex_hull(&points);
+        let corners = [(0, 0), (4, 0), (4, 4), (0, 4)].map(|(x, y)| Point { x, y });
+        assert_eq!(hull.len(), 4);
+        assert!(corners.iter().all(|corner| hull.contains(corner)));
+    }
+
+    #[test]
+    fn square_covers_its_pixels() {
+        let square = polygon(&[(0, 0), (4, 0), (4, 4), (0, 4)], false);
+        let mut covered = pixels(&square, 10, 10);
+        covered.sort();
+        let expected: Vec<(u32, u32)> = (0..4).flat_map(|x| (0..4).map(move |y| (x, y))).collect();
+        assert_eq!(covered, expected);
+    }
+
+    #[test]
+    fn fill_stays_in_the_image() {
+        let square = polygon(&[(2, 2), (30, 2), (30, 30), (2, 30)], false);
+        let covered = pixels(&square, 10, 8);
+        assert_eq!(covered.len(), 8 * 6);
+        assert!(covered.iter().all(|&(x, y)| x < 10 && y < 8));
+    }
+
+    #[test]
+    fn crossing_edges_leave_the_even_odd_hole_empty() {
+        let covered = pixels(&polygon(&star(), false), 40, 40);
+        assert!(!covered.contains(&(20, 20)), "the center is inside twice");
+        assert!(covered.contains(&(20, 6)), "a tip is inside once");
+        assert_eq!(covered.len(), covered.iter().collect::<std::collections::HashSet<_>>().len());
+
+        // the hull of the same vertices covers the center
+        let hull = pixels(&polygon(&star(), true), 40, 40);
+        assert!(hull.contains(&(20, 20)));
+        assert!(hull.len() > covered.len());
+    }
+}
@@ -0,0 +1,241 @@
+use super::polygon::fill_polygon;
+use super::{centroid, deserialize_color, gaussian, max_radius, nudge, nudge_point, random_point, random_radius, rescale_length, rescale_point, scale_point, serialize_color, translate_point, BoundingBox, Point, Shape};
+use image::Rgba;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
+use std::f32::consts::PI;
+
+/// Rectangle is an axis-aligned rectangle between 2 opposite corners
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rectangle {
+    pub points: [Point; 2],
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub color: Rgba<u8>,
+}
+
+impl Shape for Rectangle {
+    fn rasterise(&self, w: u32, h: u32, f: &mut dyn FnMut(u32, u32)) {
+        let [p1, p2] = &self.points;
+        for y in min(p1.y, p2.y) .. min(max(p1.y, p2.y), h) {
+            for x in min(p1.x, p2.x) .. min(max(p1.x, p2.x), w) {
+                f(x, y);
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::around(&self.points)
+    }
+
+    // mutate moves a corner to a random point
+    fn mutate(&mut self, rng: &mut dyn RngCore, w: u32, h: u32) {
+        let corner = rng.gen_range(0..2);
+        self.points[corner] = random_point(rng, w, h);
+    }
+
+    // perturb moves a corner by a Gaussian step
+    fn perturb(&mut self, rng: &mut dyn RngCore, sigma: f64, w: u32, h: u32) {
+        let corner = rng.gen_range(0..2);
+        self.points[corner] = nudge_point(rng, self.points[corner], sigma, w, h);
+    }
+
+    fn rescale(&mut self, sx: f64, sy: f64, w: u32, h: u32) {
+        self.points = self.points.map(|point| rescale_point(point, sx, sy, w, h));
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32, w: u32, h: u32) {
+        self.points = self.points.map(|point| translate_point(point, dx, dy, w, h));
+    }
+
+    // scale moves the corners away from the center, or closer to it
+    fn scale(&mut self, factor: f64, w: u32, h: u32) {
+        let center = centroid(&self.points);
+        self.points = self.points.map(|point| scale_point(point, center, factor, w, h));
+    }
+
+    // rotate leaves the rectangle as it is, its sides stay aligned with the image
+    fn rotate(&mut self, _angle: f64, _w: u32, _h: u32) {}
+
+    fn color(&self) -> Rgba<u8> {
+        self.color
+    }
+
+    fn color_mut(&mut self) -> &mut Rgba<u8> {
+        &mut self.color
+    }
+
+    fn svg(&self, attributes: &str) -> String {
+        let [p1, p2] = &self.points;
+        format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+            min(p1.x, p2.x), min(p1.y, p2.y), p1.x.abs_diff(p2.x), p1.y.abs_diff(p2.y), attributes
+        )
+    }
+}
+
+/// RotatedRectangle is a rectangle of the given size around its center,
+/// rotated clockwise on screen by angle radians
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RotatedRectangle {
+    pub center: Point,
+    pub width: u32,
+    pub height: u32,
+    pub angle: f32,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub color: Rgba<u8>,
+}
+
+impl RotatedRectangle {
+    /// corners returns the 4 corners of the rectangle, in order around it
+    pub fn corners(&self) -> [(f64, f64); 4] {
+        let (cx, cy) = (self.center.x as f64, self.center.y as f64);
+        let (sin, cos) = (self.angle as f64).sin_cos();
+        let (hw, hh) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+
+        [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)]
+            .map(|(dx, dy)| (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos))
+    }
+}
+
+impl Shape for RotatedRectangle {
+    fn rasterise(&self, w: u32, h: u32, f: &mut dyn FnMut(u32, u32)) {
+        fill_polygon(&self.corners(), w, h, f);
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let corners = self.corners();
+        let xs = corners.iter().map(|c| c.0);
+        let ys = corners.iter().map(|c| c.1);
+        BoundingBox {
+            xmin: xs.clone().fold(f64::INFINITY, f64::min).floor() as i32,
+            ymin: ys.clone().fold(f64::INFINITY, f64::min).floor() as i32,
+            xmax: xs.fold(f64::NEG_INFINITY, f64::max).ceil() as i32 + 1,
+            ymax: ys.fold(f64::NEG_INFINITY, f64::max).ceil() as i32 + 1,
+        }
+    }
+
+    // mutate moves the center, changes the width, the height or the angle
+    fn mutate(&mut self, rng: &mut dyn RngCore, w: u32, h: u32) {
+        match rng.gen_range(0..4) {
+            0 => self.center = random_point(rng, w, h),
+            1 => self.width = 2 * random_radius(rng, w, h),
+            2 => self.height = 2 * random_radius(rng, w, h),
+            _ => self.angle = rng.gen_range(0.0..PI),
+        }
+    }
+
+    // perturb moves the center, changes the width, the height or the angle by a
+    // Gaussian step, the angle turning the far corners by about sigma pixels
+    fn perturb(&mut self, rng: &mut dyn RngCore, sigma: f64, w: u32, h: u32) {
+        match rng.gen_range(0..4) {
+            0 => self.center = nudge_point(rng, self.center, sigma, w, h),
+            1 => self.width = nudge(rng, self.width, sigma, 2, 2 * max_radius(w, h)),
+            2 => self.height = nudge(rng, self.height, sigma, 2, 2 * max_radius(w, h)),
+            _ => {
+                let radius = (self.width.max(self.height) as f64 / 2.0).max(1.0);
+                self.angle = (self.angle + gaussian(rng, sigma / radius) as f32).rem_euclid(PI);
+            }
+        }
+    }
+
+    // rescale stretches the width and height along the axes of the rectangle,
+    // which is exact when the image keeps its proportions
+    fn rescale(&mut self, sx: f64, sy: f64, w: u32, h: u32) {
+        self.center = rescale_point(self.center, sx, sy, w, h);
+        self.width = rescale_length(self.width, sx, 2, 2 * max_radius(w, h));
+        self.height = rescale_length(self.height, sy, 2, 2 * max_radius(w, h));
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32, w: u32, h: u32) {
+        self.center = translate_point(self.center, dx, dy, w, h);
+    }
+
+    fn scale(&mut self, factor: f64, w: u32, h: u32) {
+        self.width = rescale_length(self.width, factor, 2, 2 * max_radius(w, h));
+        self.height = rescale_length(self.height, factor, 2, 2 * max_radius(w, h));
+    }
+
+    fn rotate(&mut self, angle: f64, _w: u32, _h: u32) {
+        self.angle = (self.angle + angle as f32).rem_euclid(PI);
+    }
+
+    fn color(&self) -> Rgba<u8> {
+        self.color
+    }
+
+    fn color_mut(&mut self) -> &mut Rgba<u8> {
+        &mut self.color
+    }
+
+    fn svg(&self, attributes: &str) -> String {
+        let (cx, cy) = (self.center.x as f64, self.center.y as f64);
+        format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" transform="rotate({:.3} {} {})" {}/>"#,
+            cx - self.width as f64 / 2.0, cy - self.height as f64 / 2.0, self.width, self.height,
+            self.angle.to_degrees(), cx, cy, attributes
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // pixels returns the pixels a shape covers in a w*h image, in order
+    fn pixels(shape: &dyn Shape, w: u32, h: u32) -> Vec<(u32, u32)> {
+        let mut pixels = vec![];
+        shape.rasterise(w, h, &mut |x, y| pixels.push((x, y)));
+        pixels.sort();
+        pixels
+    }
+
+    // square returns the pixels from (x, y) included to (x + size, y + size) excluded
+    fn square(x: u32, y: u32, size: u32) -> Vec<(u32, u32)> {
+        (x..x + size).flat_map(|x| (y..y + size).map(move |y| (x, y))).collect()
+    }
+
+    fn rectangle(p1: (u32, u32), p2: (u32, u32)) -> Rectangle {
+        let points = [p1, p2].map(|(x, y)| Point { x, y });
+        Rectangle { points, color: Rgba([0, 0, 0, 255]) }
+    }
+
+    fn rotated(size: u32, angle: f32) -> RotatedRectangle {
+        RotatedRectangle { center: Point { x: 10, y: 10 }, width: size, height: size, angle, color: Rgba([0, 0, 0, 255]) }
+    }
+
+    #[test]
+    fn rectangle_covers_the_pixels_between_its_corners() {
+        assert_eq!(pixels(&rectangle((2, 3), (6, 7)), 20, 20), square(2, 3, 4));
+        // the corners may be given in any order
+        assert_eq!(pixels(&rectangle((6, 3), (2, 7)), 20, 20), square(2, 3, 4));
+        assert!(pixels(&rectangle((5, 5), (5, 9)), 20, 20).is_empty());
+    }
+
+    #[test]
+    fn rectangle_is_clipped_to_the_image() {
+        assert_eq!(pixels(&rectangle((16, 16), (30, 30)), 20, 20), square(16, 16, 4));
+    }
+
+    #[test]
+    fn rotated_rectangle_without_angle_covers_a_square() {
+        assert_eq!(pixels(&rotated(4, 0.0), 20, 20), square(8, 8, 4));
+    }
+
+    #[test]
+    fn rotated_rectangle_covers_its_turned_area() {
+        // a square turned by an eighth of a turn stands on a corner
+        let angle = PI / 4.0;
+        let covered = pixels(&rotated(10, angle), 20, 20);
+        // the edges are sampled at whole pixels, so the count is only close to the area
+        assert!((90..=120).contains(&covered.len()), "{} pixels", covered.len());
+
+        let (sin, cos) = (angle as f64).sin_cos();
+        for &(x, y) in &covered {
+            let (dx, dy) = (x as f64 - 10.0, y as f64 - 10.0);
+            // the pixel is within half the side of the center along both turned axes
+            assert!((dx * cos + dy * sin).abs() <= 5.0 + 1e-9 && (dy * cos - dx * sin).abs() <= 5.0 + 1e-9, "({x}, {y})");
+        }
+        assert!(covered.contains(&(10, 3)) && !covered.contains(&(3, 3)));
+    }
+}
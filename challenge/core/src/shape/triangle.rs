@@ -0,0 +1,101 @@
+use super::{centroid, deserialize_color, nudge_point, random_point, rescale_point, rotate_point, scale_point, serialize_color, translate_point, BoundingBox, Point, Shape};
+use image::Rgba;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
+
+/// Triangle is a shape that will be drawn into the image
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Triangle {
+    pub points: [Point; 3],
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub color: Rgba<u8>
+}
+
+impl Shape for Triangle {
+    fn rasterise(&self, w: u32, h: u32, f: &mut dyn FnMut(u32, u32)) {
+        let x1 = self.points[0].x as i32;
+        let y1 = self.points[0].y as i32;
+
+        let x2 = self.points[1].x as i32;
+        let y2 = self.points[1].y as i32;
+
+        let x3 = self.points[2].x as i32;
+        let y3 = self.points[2].y as i32;
+
+        let xmin = min(x1, min(x2, x3));
+        let xmax = min(max(x1, max(x2, x3)), w as i32);
+        let ymin = min(y1, min(y2, y3));
+        let ymax = min(max(y1, max(y2, y3)), h as i32);
+
+        // pre compute all constant values
+        let x21 = x2-x1;
+        let y21 = y2-y1;
+        let s21 = y21*x1-x21*y1;
+        let x31 = x3-x1;
+        let y31 = y3-y1;
+        let s31 = y31*x1-x31*y1;
+        let x32 = x3-x2;
+        let y32 = y3-y2;
+        let s32 = y32*x2-x32*y2;
+
+        for x in xmin .. xmax {
+            for y in ymin .. ymax {
+                let sab = y*x21-x*y21 + s21 > 0;
+                if (y*x31-x*y31 + s31 > 0) == sab { continue };
+                if (y*x32-x*y32 + s32 > 0) != sab { continue };
+
+                f(x as u32, y as u32);
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::around(&self.points)
+    }
+
+    // mutate moves a vertice to a random point
+    fn mutate(&mut self, rng: &mut dyn RngCore, w: u32, h: u32) {
+        let vertice_index = rng.gen_range(0..3);
+        self.points[vertice_index] = random_point(rng, w, h);
+    }
+
+    // perturb moves a vertice by a Gaussian step
+    fn perturb(&mut self, rng: &mut dyn RngCore, sigma: f64, w: u32, h: u32) {
+        let vertice_index = rng.gen_range(0..3);
+        self.points[vertice_index] = nudge_point(rng, self.points[vertice_index], sigma, w, h);
+    }
+
+    fn rescale(&mut self, sx: f64, sy: f64, w: u32, h: u32) {
+        self.points = self.points.map(|point| rescale_point(point, sx, sy, w, h));
+    }
+
+    fn translate(&mut self, dx: i32, dy: i32, w: u32, h: u32) {
+        self.points = self.points.map(|point| translate_point(point, dx, dy, w, h));
+    }
+
+    // scale moves the vertices away from the centroid, or closer to it
+    fn scale(&mut self, factor: f64, w: u32, h: u32) {
+        let center = centroid(&self.points);
+        self.points = self.points.map(|point| scale_point(point, center, factor, w, h));
+    }
+
+    // rotate turns the vertices around the centroid
+    fn rotate(&mut self, angle: f64, w: u32, h: u32) {
+        let center = centroid(&self.points);
+        self.points = self.points.map(|point| rotate_point(point, center, angle, w, h));
+    }
+
+    fn color(&self) -> Rgba<u8> {
+        self.color
+    }
+
+    fn color_mut(&mut self) -> &mut Rgba<u8> {
+        &mut self.color
+    }
+
+    fn svg(&self, attributes: &str) -> String {
+        let [p1, p2, p3] = &self.points;
+        format!(r#"<polygon points="{},{} {},{} {},{}" {}/>"#, p1.x, p1.y, p2.x, p2.y, p3.x, p3.y, attributes)
+    }
+}
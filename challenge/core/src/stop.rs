@@ -0,0 +1,172 @@
+use crate::evolver::Evolver;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Stop holds the conditions that end a run, the first one met ending it.
+/// A run without any condition never stops
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stop {
+    /// number of epochs to run
+    pub epochs: Option<u64>,
+    /// wall-clock time the run may take
+    pub time: Option<Duration>,
+    /// best distance to reach
+    pub distance: Option<f64>,
+    /// number of epochs in a row without getting closer to the target
+    pub stagnation: Option<u64>,
+}
+
+impl Stop {
+    /// watch starts checking the conditions on a run, from its current epoch and best distance
+    pub fn watch(self, evolver: &Evolver) -> Watch {
+        Watch { stop: self, begin: Instant::now(), done: 0, best: evolver.best_distance(), improved: 0 }
+    }
+}
+
+/// StopReason is the condition that ended a run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    /// the number of epochs was run
+    Epochs(u64),
+    /// the time ran out
+    Time(Duration),
+    /// the best distance reached the target distance
+    Distance(f64),
+    /// the best distance didn't improve for the number of epochs
+    Stagnation(u64),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Epochs(epochs) => write!(f, "ran {} epochs", epochs),
+            StopReason::Time(time) => write!(f, "ran out of time after {:.3} seconds", time.as_secs_f64()),
+            StopReason::Distance(distance) => write!(f, "reached distance {}", distance),
+            StopReason::Stagnation(epochs) => write!(f, "no improvement in {} epochs", epochs),
+        }
+    }
+}
+
+/// Watch tells when a run meets one of the conditions of a Stop
+#[derive(Clone, Debug)]
+pub struct Watch {
+    stop: Stop,
+    begin: Instant,
+    // epochs run since the watch started
+    done: u64,
+    // best distance and the epoch it was reached, counted as done
+    best: f64,
+    improved: u64,
+}
+
+impl Watch {
+    /// check counts an epoch of the run and returns the condition it meets, if any.
+    /// It is called after every step
+    pub fn check(&mut self, evolver: &Evolver) -> Option<StopReason> {
+        self.count(evolver.best_distance())
+    }
+
+    // count counts an epoch after which the run is at the best distance
+    fn count(&mut self, best: f64) -> Option<StopReason> {
+        self.done += 1;
+        if best < self.best {
+            self.best = best;
+            self.improved = self.done;
+        }
+
+        let stop = &self.stop;
+        if stop.distance.is_some_and(|distance| self.best <= distance) {
+            return stop.distance.map(StopReason::Distance);
+        }
+        if stop.epochs.is_some_and(|epochs| self.done >= epochs) {
+            return stop.epochs.map(StopReason::Epochs);
+        }
+        if stop.stagnation.is_some_and(|epochs| self.done - self.improved >= epochs) {
+            return stop.stagnation.map(StopReason::Stagnation);
+        }
+        if stop.time.is_some_and(|time| self.begin.elapsed() >= time) {
+            return Some(StopReason::Time(self.begin.elapsed()));
+        }
+        None
+    }
+
+    /// rescaled measures the stagnation from the best distance of a run that was
+    /// just rescaled, since distances at another size don't compare
+    pub fn rescaled(&mut self, evolver: &Evolver) {
+        self.best = evolver.best_distance();
+        self.improved = self.done;
+    }
+
+    /// done returns the number of epochs run since the watch started
+    pub fn done(&self) -> u64 {
+        self.done
+    }
+
+    /// progress returns the share of the epochs or of the time already spent, the
+    /// closest to its end, or None when neither is limited
+    pub fn progress(&self) -> Option<f64> {
+        let epochs = self.stop.epochs.map(|epochs| self.done as f64 / epochs.max(1) as f64);
+        let time = self.stop.time.map(|time| self.begin.elapsed().as_secs_f64() / time.as_secs_f64().max(f64::EPSILON));
+        match (epochs, time) {
+            (Some(epochs), Some(time)) => Some(epochs.max(time).min(1.0)),
+            (progress, None) | (None, progress) => progress.map(|progress| progress.min(1.0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // watch starts watching a run at distance 10
+    fn watch(stop: Stop) -> Watch {
+        Watch { stop, begin: Instant::now(), done: 0, best: 10.0, improved: 0 }
+    }
+
+    #[test]
+    fn stops_at_the_distance() {
+        let mut watch = watch(Stop { distance: Some(5.0), ..Stop::default() });
+        assert_eq!(watch.count(8.0), None);
+        assert_eq!(watch.count(5.0), Some(StopReason::Distance(5.0)));
+    }
+
+    #[test]
+    fn stops_when_the_distance_stagnates() {
+        let mut watch = watch(Stop { stagnation: Some(3), ..Stop::default() });
+        // the first epoch improves, the stagnation counts from it
+        for best in [9.0, 9.0, 9.0] {
+            assert_eq!(watch.count(best), None);
+        }
+        assert_eq!(watch.count(9.0), Some(StopReason::Stagnation(3)));
+
+        // an improvement starts the count over
+        let mut watch = self::watch(Stop { stagnation: Some(3), ..Stop::default() });
+        for best in [10.0, 10.0, 9.5, 9.5, 9.5] {
+            assert_eq!(watch.count(best), None);
+        }
+        assert_eq!(watch.count(9.5), Some(StopReason::Stagnation(3)));
+    }
+
+    #[test]
+    fn stops_after_the_epochs() {
+        let mut watch = watch(Stop { epochs: Some(2), ..Stop::default() });
+        assert_eq!(watch.count(9.0), None);
+        assert_eq!(watch.progress(), Some(0.5));
+        assert_eq!(watch.count(8.0), Some(StopReason::Epochs(2)));
+        assert_eq!(watch.done(), 2);
+    }
+
+    #[test]
+    fn stops_when_the_time_is_up() {
+        let mut watch = watch(Stop { time: Some(Duration::ZERO), ..Stop::default() });
+        assert!(matches!(watch.count(9.0), Some(StopReason::Time(_))));
+        assert_eq!(watch.progress(), Some(1.0));
+    }
+
+    #[test]
+    fn runs_forever_without_conditions() {
+        let mut watch = watch(Stop::default());
+        assert!((0..1000).all(|epoch| watch.count(10.0 - epoch as f64 / 1000.0).is_none()));
+        assert_eq!(watch.progress(), None);
+    }
+}
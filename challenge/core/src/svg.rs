@@ -0,0 +1,83 @@
+use crate::shape::{BoundingBox, Primitive, Shape};
+use image::Rgba;
+use std::fmt::Write;
+
+/// svg returns an SVG document of the given size drawing the shapes on a
+/// background made of colored tiles, with one element per shape in stacking order.
+/// The shapes are moved by half a pixel, so an SVG renderer covers the pixels
+/// the rasteriser fills rather than the ones half a pixel up and left
+pub fn svg(shapes: &[Primitive], width: u32, height: u32, background: &[(BoundingBox, Rgba<u8>)]) -> String {
+    let mut document = String::new();
+
+    // writing to a String never fails
+    let _ = writeln!(document, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#, w = width, h = height);
+    // transparent tiles are left out, the other ones are rectangles under the shapes
+    for (area, color) in background.iter().filter(|(_, color)| color[3] > 0) {
+        let position = match (area.xmin, area.ymin) {
+            (0, 0) => String::new(),
+            (x, y) => format!(r#"x="{}" y="{}" "#, x, y),
+        };
+        let opacity = match color[3] {
+            255 => String::new(),
+            alpha => format!(r#" fill-opacity="{:.3}""#, alpha as f32 / 255.0),
+        };
+        let _ = writeln!(
+            document, r##"<rect {}width="{}" height="{}" fill="#{:02x}{:02x}{:02x}"{}/>"##,
+            position, area.xmax - area.xmin, area.ymax - area.ymin, color[0], color[1], color[2], opacity,
+        );
+    }
+
+    // the rasteriser paints a pixel when its corner is inside the shape,
+    // SVG when its center is, so the shapes are moved by half a pixel
+    let _ = writeln!(document, r#"<g transform="translate(0.5 0.5)" fill-rule="evenodd">"#);
+    for shape in shapes {
+        let color = shape.color();
+        let fill = format!(
+            r##"fill="#{:02x}{:02x}{:02x}" fill-opacity="{:.3}""##,
+            color[0], color[1], color[2],
+            color[3] as f32 / 255.0,
+        );
+        let _ = writeln!(document, "{}", shape.svg(&fill));
+    }
+    let _ = writeln!(document, "</g>");
+    let _ = writeln!(document, "</svg>");
+
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::svg;
+    use crate::background::Background;
+    use crate::shape::{Point, Primitive, Triangle};
+    use crate::ImgRGBA;
+    use image::Rgba;
+
+    // triangle returns a shape with the given vertices and color
+    fn triangle(points: [(u32, u32); 3], color: [u8; 4]) -> Primitive {
+        Primitive::Triangle(Triangle { points: points.map(|(x, y)| Point { x, y }), color: Rgba(color) })
+    }
+
+    #[test]
+    fn draws_a_polygon_per_triangle_on_the_background() {
+        let shapes = [triangle([(0, 0), (10, 0), (0, 8)], [255, 0, 0, 255]), triangle([(2, 3), (9, 7), (4, 8)], [16, 32, 48, 128])];
+        let background = Background::White.canvas(&ImgRGBA::new(12, 9)).tiles(12, 9);
+
+        let expected = concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="12" height="9" viewBox="0 0 12 9">"#, "\n",
+            r##"<rect width="12" height="9" fill="#ffffff"/>"##, "\n",
+            r#"<g transform="translate(0.5 0.5)" fill-rule="evenodd">"#, "\n",
+            r##"<polygon points="0,0 10,0 0,8" fill="#ff0000" fill-opacity="1.000"/>"##, "\n",
+            r##"<polygon points="2,3 9,7 4,8" fill="#102030" fill-opacity="0.502"/>"##, "\n",
+            "</g>\n",
+            "</svg>\n",
+        );
+        assert_eq!(svg(&shapes, 12, 9, &background), expected);
+    }
+
+    #[test]
+    fn leaves_transparent_tiles_out() {
+        let background = Background::Transparent.canvas(&ImgRGBA::new(4, 4)).tiles(4, 4);
+        assert!(!svg(&[], 4, 4, &background).contains("<rect"));
+    }
+}
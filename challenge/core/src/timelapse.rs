@@ -0,0 +1,254 @@
+use crate::ImgRGBA;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageError};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+// speed of the GIF color quantization, from 1 (best colors) to 30 (fastest)
+const GIF_SPEED: i32 = 10;
+// most frames a timelapse holds besides the last one, even so halving them
+// keeps the frames taken every other stride
+const MAX_FRAMES: usize = 256;
+
+/// Timelapse collects snapshots of a run to save them as an animation that
+/// plays forever, an animated GIF or an animated PNG (APNG). It keeps at most
+/// MAX_FRAMES frames and the last one: when there are more, every other frame is
+/// dropped and the next ones are kept half as often, so long runs speed up
+#[derive(Clone, Debug)]
+pub struct Timelapse {
+    frames: Vec<ImgRGBA>,
+    // last frame pushed, when it falls between kept frames
+    last: Option<ImgRGBA>,
+    // number of frames pushed, and how many pushes there are between 2 kept frames
+    pushed: usize,
+    stride: usize,
+    // time each frame is shown, in milliseconds
+    delay: u16,
+}
+
+impl Default for Timelapse {
+    fn default() -> Self {
+        Timelapse::new(0)
+    }
+}
+
+impl Timelapse {
+    /// new creates an empty timelapse showing each frame for the delay in milliseconds
+    pub fn new(delay: u16) -> Timelapse {
+        Timelapse { frames: Vec::new(), last: None, pushed: 0, stride: 1, delay }
+    }
+
+    /// push adds a frame after the others, every frame must have the size of the first one
+    pub fn push(&mut self, frame: ImgRGBA) {
+        if self.pushed.is_multiple_of(self.stride) {
+            self.frames.push(frame);
+            self.last = None;
+        } else {
+            self.last = Some(frame);
+        }
+        self.pushed += 1;
+
+        if self.frames.len() > MAX_FRAMES {
+            self.frames = self.frames.drain(..).step_by(2).collect();
+            self.stride *= 2;
+        }
+    }
+
+    /// len returns the number of frames of the animation
+    pub fn len(&self) -> usize {
+        self.frames.len() + self.last.is_some() as usize
+    }
+
+    /// is_empty tells whether the timelapse has no frame
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // frames returns the frames of the animation in order
+    fn frames(&self) -> impl Iterator<Item = &ImgRGBA> {
+        self.frames.iter().chain(&self.last)
+    }
+
+    /// save writes the animation to a file, as a GIF when its extension is gif and
+    /// as an APNG when it is png or apng
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TimelapseError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        match extension.to_ascii_lowercase().as_str() {
+            "gif" => self.save_gif(path),
+            "png" | "apng" => self.save_apng(path),
+            _ => Err(TimelapseError::Extension(extension.to_string())),
+        }
+    }
+
+    fn save_gif(&self, path: &Path) -> Result<(), TimelapseError> {
+        let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), GIF_SPEED);
+        encoder.set_repeat(Repeat::Infinite)?;
+
+        let delay = Delay::from_numer_denom_ms(self.delay as u32, 1);
+        encoder.encode_frames(self.frames().map(|frame| Frame::from_parts(frame.clone(), 0, 0, delay)))?;
+        Ok(())
+    }
+
+    fn save_apng(&self, path: &Path) -> Result<(), TimelapseError> {
+        let Some(first) = self.frames.first() else {
+            return Err(TimelapseError::NoFrames);
+        };
+
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), first.width(), first.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.len() as u32, 0)?;
+        encoder.set_frame_delay(self.delay, 1000)?;
+
+        let mut writer = encoder.write_header()?;
+        for frame in self.frames() {
+            writer.write_image_data(frame.as_raw())?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// TimelapseError is returned when a timelapse can't be saved
+#[derive(Debug)]
+pub enum TimelapseError {
+    Io(io::Error),
+    Gif(ImageError),
+    Png(png::EncodingError),
+    /// the file extension is neither gif, png nor apng
+    Extension(String),
+    /// an animated PNG needs at least one frame
+    NoFrames,
+}
+
+impl fmt::Display for TimelapseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimelapseError::Io(err) => write!(f, "{}", err),
+            TimelapseError::Gif(err) => write!(f, "{}", err),
+            TimelapseError::Png(err) => write!(f, "{}", err),
+            TimelapseError::Extension(extension) => write!(f, "unknown animation format {:?}, use gif, png or apng", extension),
+            TimelapseError::NoFrames => write!(f, "the timelapse has no frames"),
+        }
+    }
+}
+
+impl std::error::Error for TimelapseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TimelapseError::Io(err) => Some(err),
+            TimelapseError::Gif(err) => Some(err),
+            TimelapseError::Png(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TimelapseError {
+    fn from(err: io::Error) -> Self {
+        TimelapseError::Io(err)
+    }
+}
+
+impl From<ImageError> for TimelapseError {
+    fn from(err: ImageError) -> Self {
+        TimelapseError::Gif(err)
+    }
+}
+
+impl From<png::EncodingError> for TimelapseError {
+    fn from(err: png::EncodingError) -> Self {
+        TimelapseError::Png(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifDecoder;
+    use image::{AnimationDecoder, Rgba};
+    use std::{env, fs};
+
+    // frame returns a 4x3 image of a single shade, the shade telling frames apart
+    fn frame(shade: u8) -> ImgRGBA {
+        ImgRGBA::from_pixel(4, 3, Rgba([shade, shade, shade, 255]))
+    }
+
+    // timelapse returns a timelapse of the given number of frames, the shade of
+    // each one being its index
+    fn timelapse(frames: usize) -> Timelapse {
+        let mut timelapse = Timelapse::new(80);
+        for i in 0..frames {
+            timelapse.push(frame(i as u8));
+        }
+        timelapse
+    }
+
+    fn shades(timelapse: &Timelapse) -> Vec<u8> {
+        timelapse.frames().map(|frame| frame.get_pixel(0, 0)[0]).collect()
+    }
+
+    fn path(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!("evolve-core-timelapse-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn halves_the_frames_past_the_limit() {
+        let pushed = MAX_FRAMES + 2;
+        let timelapse = timelapse(pushed);
+        // every other frame is left and the last one stays
+        let mut expected: Vec<u8> = (0..pushed).step_by(2).map(|i| i as u8).collect();
+        expected.push((pushed - 1) as u8);
+        assert_eq!(shades(&timelapse), expected);
+        assert_eq!(timelapse.len(), expected.len());
+    }
+
+    #[test]
+    fn never_holds_more_than_the_limit() {
+        let mut timelapse = Timelapse::new(80);
+        for i in 0..10 * MAX_FRAMES {
+            timelapse.push(frame(i as u8));
+            assert!(timelapse.len() <= MAX_FRAMES + 1);
+        }
+        assert!(timelapse.len() > MAX_FRAMES / 2);
+    }
+
+    #[test]
+    fn saves_a_gif_with_every_frame_and_the_delay() {
+        let path = path("frames.gif");
+        timelapse(5).save(&path).unwrap();
+        let frames = GifDecoder::new(File::open(&path).unwrap()).unwrap().into_frames().collect_frames().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(frames.len(), 5);
+        assert!(frames.iter().all(|frame| frame.delay().numer_denom_ms() == (80, 1)));
+    }
+
+    #[test]
+    fn saves_an_apng_with_every_frame_and_the_delay() {
+        let path = path("frames.png");
+        timelapse(5).save(&path).unwrap();
+        let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames, 5);
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut shades = vec![];
+        for _ in 0..5 {
+            reader.next_frame(&mut buffer).unwrap();
+            let delay = reader.info().frame_control.unwrap();
+            assert_eq!((delay.delay_num, delay.delay_den), (80, 1000));
+            shades.push(buffer[0]);
+        }
+        fs::remove_file(&path).unwrap();
+        assert_eq!(shades, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn rejects_other_extensions() {
+        assert!(matches!(timelapse(2).save(path("frames.mp4")), Err(TimelapseError::Extension(_))));
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use evolve_core::{Backend, Config, Evolver, Growth, Objective, Structure};
+
+// assert_same_distances runs the full and the layered backends side by side and
+// checks that every epoch measures the same distance and keeps the same shapes
+fn assert_same_distances(config: Config, epochs: u64) {
+    let mut full = Evolver::new(common::target(), Config { backend: Backend::Full, ..config.clone() });
+    let mut layered = Evolver::new(common::target(), Config { backend: Backend::Layered, ..config });
+    assert_eq!(full.distance(), layered.distance());
+
+    for _ in 0..epochs {
+        let (expected, found) = (full.step(), layered.step());
+        assert_eq!((found.kind, found.index), (expected.kind, expected.index), "epoch {}", expected.epoch);
+        assert_eq!(found.distance, expected.distance, "epoch {}", expected.epoch);
+        assert_eq!(found.accepted, expected.accepted, "epoch {}", expected.epoch);
+        assert_eq!(layered.shapes(), full.shapes(), "epoch {}", expected.epoch);
+    }
+    assert_eq!(layered.best_distance(), full.best_distance());
+}
+
+fn config() -> Config {
+    Config { shapes: 12, seed: Some(5), ..Config::default() }
+}
+
+#[test]
+fn layered_matches_full() {
+    assert_same_distances(config(), 500);
+}
+
+#[test]
+fn layered_matches_full_with_structure() {
+    let structure = Structure { swap: 0.05, to_top: 0.05, to_bottom: 0.05, delete: 0.05, insert: 0.05 };
+    let growth = Growth { start: 6, patience: 20 };
+    assert_same_distances(Config { structure, growth: Some(growth), ..config() }, 500);
+}
+
+#[test]
+fn layered_matches_full_with_structure_and_threads() {
+    let structure = Structure { swap: 0.1, to_top: 0.05, to_bottom: 0.05, delete: 0.05, insert: 0.1 };
+    assert_same_distances(Config { structure, threads: 3, ..config() }, 500);
+}
+
+#[test]
+fn layered_matches_full_with_ssim() {
+    // the full backend measures every 7x7 window each epoch, which is slow without optimisations
+    assert_same_distances(Config { objective: Objective::Ssim, ..config() }, 50);
+}
+
+#[test]
+fn layered_matches_full_with_edges() {
+    assert_same_distances(Config { objective: Objective::Edges, ..config() }, 500);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use evolve_core::{Acceptance, Annealing, Checkpoint, Config, Evolver, Genetic, Growth, Mutation, Schedule, Structure};
+use std::env;
+use std::fs;
+
+// assert_resumes_exactly checks that a run checkpointed after some epochs, saved,
+// loaded and resumed ends exactly as the same run without a break
+fn assert_resumes_exactly(name: &str, config: Config) {
+    let (epochs, total) = (150, 400);
+
+    let mut whole = Evolver::new(common::target(), config.clone());
+    whole.run(total);
+
+    let mut first = Evolver::new(common::target(), config);
+    first.run(epochs);
+    let path = env::temp_dir().join(format!("evolve-core-checkpoint-{}-{}.json", name, std::process::id()));
+    first.checkpoint().save(&path).unwrap();
+    let checkpoint = Checkpoint::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut resumed = Evolver::resume(common::target(), checkpoint).unwrap();
+    assert_eq!(resumed.epoch(), epochs);
+    resumed.run(total - epochs);
+
+    assert_eq!(resumed.epoch(), whole.epoch());
+    assert_eq!(resumed.best_shapes(), whole.best_shapes());
+    assert_eq!(resumed.shapes(), whole.shapes());
+    assert_eq!(resumed.best_distance().to_bits(), whole.best_distance().to_bits());
+    assert_eq!(resumed.distance().to_bits(), whole.distance().to_bits());
+}
+
+fn config() -> Config {
+    Config { shapes: 10, seed: Some(11), ..Config::default() }
+}
+
+#[test]
+fn resumes_hill_climber() {
+    assert_resumes_exactly("hill-climber", config());
+}
+
+#[test]
+fn resumes_annealing() {
+    let annealing = Annealing { schedule: Schedule::Reheating, start: 0.5, end: 0.005, epochs: 100 };
+    assert_resumes_exactly("annealing", Config { acceptance: Acceptance::Annealing(annealing), ..config() });
+}
+
+#[test]
+fn resumes_gaussian_mutations_with_threads() {
+    assert_resumes_exactly("gaussian", Config { mutation: Mutation::Gaussian, threads: 2, ..config() });
+}
+
+#[test]
+fn resumes_genetic_algorithm() {
+    let genetic = Genetic { population: 6, ..Genetic::default() };
+    assert_resumes_exactly("genetic", Config { genetic: Some(genetic), ..config() });
+}
+
+#[test]
+fn resumes_growing_and_restructured_shapes() {
+    let structure = Structure { swap: 0.05, delete: 0.05, insert: 0.05, ..Structure::default() };
+    let growth = Growth { start: 3, patience: 20 };
+    assert_resumes_exactly("growth", Config { structure, growth: Some(growth), ..config() });
+}
+
+#[test]
+fn resumes_annealing_after_rescale() {
+    let annealing = Annealing { schedule: Schedule::Exponential, start: 0.5, end: 0.005, epochs: 100 };
+    let config = Config { acceptance: Acceptance::Annealing(annealing), ..config() };
+    let half = image::imageops::thumbnail(&common::target(), 20, 15);
+
+    // the schedule starts over at the full size
+    let mut evolver = Evolver::new(half, config);
+    evolver.run(150);
+    evolver.rescale(common::target());
+    assert_eq!(evolver.temperature(), annealing.start);
+    evolver.run(50);
+
+    let resumed = Evolver::resume(common::target(), evolver.checkpoint()).unwrap();
+    assert_eq!(resumed.temperature().to_bits(), evolver.temperature().to_bits());
+    assert_eq!(resumed.temperature().to_bits(), annealing.temperature(50).to_bits());
+}
@@ -0,0 +1,13 @@
+use evolve_core::ImgRGBA;
+use image::Rgba;
+
+// target returns a small image with a gradient and a darker square, quick to evolve
+pub fn target() -> ImgRGBA {
+    ImgRGBA::from_fn(40, 30, |x, y| {
+        if (10..25).contains(&x) && (8..20).contains(&y) {
+            Rgba([40, 60, 160, 255])
+        } else {
+            Rgba([(x * 6) as u8, (y * 8) as u8, 200, 255])
+        }
+    })
+}
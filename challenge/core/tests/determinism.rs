@@ -0,0 +1,51 @@
+mod common;
+
+use evolve_core::{Config, Evolver, Genetic};
+
+// run evolves the test target for a number of epochs with a config
+fn run(config: &Config, epochs: u64) -> Evolver {
+    let mut evolver = Evolver::new(common::target(), config.clone());
+    evolver.run(epochs);
+    evolver
+}
+
+// assert_same_run checks that 2 runs with the same seed and config end with the same shapes and distance
+fn assert_same_run(config: Config) {
+    let (first, second) = (run(&config, 300), run(&config, 300));
+    assert_eq!(first.best_shapes(), second.best_shapes());
+    assert_eq!(first.best_distance().to_bits(), second.best_distance().to_bits());
+    assert_eq!(first.shapes(), second.shapes());
+}
+
+#[test]
+fn same_seed_same_run() {
+    assert_same_run(Config { shapes: 10, seed: Some(7), ..Config::default() });
+}
+
+#[test]
+fn same_seed_same_run_with_threads() {
+    assert_same_run(Config { shapes: 10, seed: Some(7), threads: 3, ..Config::default() });
+}
+
+#[test]
+fn same_seed_same_run_with_genetic_algorithm() {
+    let genetic = Genetic { population: 6, ..Genetic::default() };
+    assert_same_run(Config { shapes: 10, seed: Some(7), genetic: Some(genetic), ..Config::default() });
+}
+
+#[test]
+fn genetic_algorithm_runs_the_same_on_threads() {
+    // the threads only share the genomes of a generation, so they don't change the run
+    let genetic = Genetic { population: 7, ..Genetic::default() };
+    let config = Config { shapes: 10, seed: Some(7), genetic: Some(genetic), ..Config::default() };
+    let (single, threaded) = (run(&config, 100), run(&Config { threads: 3, ..config.clone() }, 100));
+    assert_eq!(threaded.best_shapes(), single.best_shapes());
+    assert_eq!(threaded.best_distance().to_bits(), single.best_distance().to_bits());
+}
+
+#[test]
+fn other_seed_other_run() {
+    let first = run(&Config { shapes: 10, seed: Some(7), ..Config::default() }, 300);
+    let second = run(&Config { shapes: 10, seed: Some(8), ..Config::default() }, 300);
+    assert_ne!(first.best_shapes(), second.best_shapes());
+}
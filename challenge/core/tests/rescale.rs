@@ -0,0 +1,33 @@
+mod common;
+
+use evolve_core::{Config, Evolver, Primitive};
+use image::imageops::{self, FilterType};
+
+#[test]
+fn rescale_stretches_the_shapes_and_measures_the_new_target() {
+    let half = imageops::resize(&common::target(), 20, 15, FilterType::Triangle);
+    let mut evolver = Evolver::new(half, Config { shapes: 10, seed: Some(3), ..Config::default() });
+    evolver.run(100);
+    let (shapes, distance) = (evolver.shapes().to_vec(), evolver.distance());
+
+    evolver.rescale(common::target());
+    assert_eq!(evolver.target().dimensions(), (40, 30));
+    assert_eq!(evolver.render().dimensions(), (40, 30));
+
+    // the target is twice as large, so every vertex lands exactly twice as far
+    assert_eq!(evolver.shapes().len(), shapes.len());
+    for (found, before) in evolver.shapes().iter().zip(&shapes) {
+        let (Primitive::Triangle(found), Primitive::Triangle(before)) = (found, before) else {
+            panic!("expected triangles, found {:?} and {:?}", found, before);
+        };
+        for (point, start) in found.points.iter().zip(&before.points) {
+            assert_eq!((point.x, point.y), (start.x * 2, start.y * 2));
+        }
+    }
+
+    // the distance is measured again, as a run starting from these shapes would
+    assert_ne!(evolver.distance(), distance);
+    let resumed = Evolver::resume(common::target(), evolver.checkpoint()).unwrap();
+    assert_eq!(evolver.distance().to_bits(), resumed.distance().to_bits());
+    assert_eq!(evolver.best_distance().to_bits(), resumed.best_distance().to_bits());
+}